@@ -1,13 +1,19 @@
 mod utils;
 
 use itertools::Itertools;
-use search_shortcuts::query_to_url;
+use search_shortcuts::api::{self, ApiError, BatchRequest};
+use search_shortcuts::errors::Error as ShortcutsError;
+use search_shortcuts::{help, Registry, Resolver};
 use serde::Deserialize;
+use std::sync::{Arc, Mutex, PoisonError};
 use worker::*;
 
 #[derive(Debug, Deserialize)]
 struct Args {
     q: Option<String>,
+    /// Shows how the query resolves instead of redirecting, as JSON for
+    /// `explain=json` and HTML otherwise.
+    explain: Option<String>,
 }
 
 fn default_headers(content_type: Option<&str>) -> Result<Headers> {
@@ -72,41 +78,176 @@ fn default_headers(content_type: Option<&str>) -> Result<Headers> {
     Ok(headers)
 }
 
-fn redirect(query: &str) -> Result<Response> {
+/// `sec_fetch_site` is the request's `Sec-Fetch-Site` header, which decides
+/// whether urls from the query need an interstitial page.
+fn redirect(query: &str, resolver: &Resolver, sec_fetch_site: Option<&str>) -> Result<Response> {
     let args: Args = serde_qs::from_str(query)
         .map_err(|err| format!("Failed to parse query string: {:?}", err))?;
     match args.q {
-        Some(query) => {
-            let headers = {
+        Some(query) if args.explain.is_some() => explain(resolver, &query, args.explain.as_deref()),
+        Some(query) if help::is_help_query(resolver, &query) => help_page(resolver),
+        Some(query) => match resolver.resolve(&query) {
+            Ok(resolution)
+                if resolver
+                    .safeguards()
+                    .needs_interstitial(&resolution, sec_fetch_site) =>
+            {
+                let headers = default_headers(Some("text/html"))?;
+                let html = help::render_interstitial(resolver, &query, &resolution.url);
+                Ok(Response::from_html(html)?.with_headers(headers))
+            }
+            Ok(resolution) => {
                 let mut headers = default_headers(None)?;
-                let redirect_url = query_to_url(&query)
-                    .map_err(|err| format!("Failed to get redirect url: {:?}", err))?;
-                headers.set("Location", redirect_url.as_str())?;
-                headers
-            };
-            Ok(Response::empty()?.with_status(303).with_headers(headers))
-        }
+                headers.set("Location", resolution.url.as_str())?;
+                Ok(Response::empty()?.with_status(303).with_headers(headers))
+            }
+            Err(err) => error_page(resolver, &query, &err),
+        },
         None => index_page(),
     }
 }
 
+fn error_page(resolver: &Resolver, query: &str, err: &ShortcutsError) -> Result<Response> {
+    if !err.is_user_error() {
+        console_error!("Error resolving {:?}: {}", query, err);
+    }
+    let headers = default_headers(Some("text/html"))?;
+    Ok(
+        Response::from_html(help::render_error(resolver, query, err))?
+            .with_status(err.status())
+            .with_headers(headers),
+    )
+}
+
+fn explain(resolver: &Resolver, query: &str, format: Option<&str>) -> Result<Response> {
+    let explanation = resolver.explain(query);
+    match format {
+        Some("json") => {
+            let headers = default_headers(Some("application/json"))?;
+            Ok(Response::from_json(&explanation)?.with_headers(headers))
+        }
+        _ => {
+            let headers = default_headers(Some("text/html"))?;
+            Ok(Response::from_html(explanation.to_html())?.with_headers(headers))
+        }
+    }
+}
+
 fn index_page() -> Result<Response> {
     let headers = default_headers(Some("text/html"))?;
     Ok(Response::from_html(include_str!("../../resources/index.html"))?.with_headers(headers))
 }
 
-fn osdf(_req: Request, _ctx: RouteContext<()>) -> Result<Response> {
+fn help_page(resolver: &Resolver) -> Result<Response> {
+    let headers = default_headers(Some("text/html"))?;
+    Ok(Response::from_html(help::render(resolver))?.with_headers(headers))
+}
+
+fn show_help(_req: Request, ctx: RouteContext<Arc<Resolver>>) -> Result<Response> {
+    help_page(&ctx.data)
+}
+
+fn api_headers() -> Result<Headers> {
+    let mut headers = default_headers(Some("application/json"))?;
+    for (name, value) in api::CORS_HEADERS {
+        headers.set(name, value)?;
+    }
+    Ok(headers)
+}
+
+fn api_resolve(req: Request, ctx: RouteContext<Arc<Resolver>>) -> Result<Response> {
+    let args: Args = serde_qs::from_str(req.url()?.query().unwrap_or_default())
+        .map_err(|err| format!("Failed to parse query string: {:?}", err))?;
+    let query = match args.q {
+        Some(query) => query,
+        None => {
+            return Ok(Response::from_json(&ApiError::new("missing q parameter"))?
+                .with_status(400)
+                .with_headers(api_headers()?))
+        }
+    };
+    let result = api::resolve(&ctx.data, &query);
+    let status = if result.is_ok() { 200 } else { 422 };
+    Ok(Response::from_json(&result)?
+        .with_status(status)
+        .with_headers(api_headers()?))
+}
+
+async fn api_resolve_batch(mut req: Request, ctx: RouteContext<Arc<Resolver>>) -> Result<Response> {
+    let batch: BatchRequest = match req.json().await {
+        Ok(batch) => batch,
+        Err(err) => {
+            return Ok(Response::from_json(&ApiError::new(err.to_string()))?
+                .with_status(400)
+                .with_headers(api_headers()?))
+        }
+    };
+    let response = match api::resolve_batch(&ctx.data, &batch) {
+        Ok(results) => Response::from_json(&results)?,
+        Err(err) => Response::from_json(&err)?.with_status(413),
+    };
+    Ok(response.with_headers(api_headers()?))
+}
+
+fn api_preflight(_req: Request, _ctx: RouteContext<Arc<Resolver>>) -> Result<Response> {
+    Ok(Response::empty()?
+        .with_status(204)
+        .with_headers(api_headers()?))
+}
+
+fn osdf(_req: Request, _ctx: RouteContext<Arc<Resolver>>) -> Result<Response> {
     let headers = default_headers(Some("application/opensearchdescription+xml"))?;
     Ok(Response::from_html(include_str!("../../resources/osdf.xml"))?.with_headers(headers))
 }
 
-fn index(req: Request, _ctx: RouteContext<()>) -> Result<Response> {
+fn suggest(req: Request, ctx: RouteContext<Arc<Resolver>>) -> Result<Response> {
+    let args: Args = serde_qs::from_str(req.url()?.query().unwrap_or_default())
+        .map_err(|err| format!("Failed to parse query string: {:?}", err))?;
+    let suggestions = ctx.data.suggest(args.q.as_deref().unwrap_or_default());
+    let headers = default_headers(Some("application/x-suggestions+json"))?;
+    Ok(Response::from_json(&suggestions)?.with_headers(headers))
+}
+
+fn index(req: Request, ctx: RouteContext<Arc<Resolver>>) -> Result<Response> {
+    let sec_fetch_site = req.headers().get("Sec-Fetch-Site")?;
     match req.url()?.query() {
-        Some(query) => redirect(query),
+        Some(query) => redirect(query, &ctx.data, sec_fetch_site.as_deref()),
         None => index_page(),
     }
 }
 
+/// Loads the shortcuts from the `SHORTCUTS_CONFIG` variable, which holds the
+/// contents of a TOML config file, falling back to the default shortcuts if
+/// it isn't set.
+fn load_registry(config: Option<&str>) -> Result<Registry> {
+    match config {
+        Some(config) => Registry::from_toml(config)
+            .map_err(|err| format!("Failed to load shortcuts: {:?}", err).into()),
+        None => Ok(Registry::default()),
+    }
+}
+
+/// The resolver and the `SHORTCUTS_CONFIG` it was built from, kept between
+/// requests as building it parses the config and compiles every pattern.
+static RESOLVER: Mutex<Option<(Option<String>, Arc<Resolver>)>> = Mutex::new(None);
+
+fn resolver(env: &Env) -> Result<Arc<Resolver>> {
+    let config = env
+        .var("SHORTCUTS_CONFIG")
+        .ok()
+        .map(|config| config.to_string());
+    let mut cached = RESOLVER.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((built_from, resolver)) = cached.as_ref() {
+        if *built_from == config {
+            return Ok(resolver.clone());
+        }
+    }
+    let registry = load_registry(config.as_deref())?;
+    let resolver = Arc::new(Resolver::builder().registry(registry).build());
+    *cached = Some((config, resolver.clone()));
+    Ok(resolver)
+}
+
 #[event(fetch)]
 pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
     // Optionally, get more helpful error messages written to the console in the case of a panic.
@@ -115,13 +256,18 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
     // Optionally, use the Router to handle matching endpoints, use ":name" placeholders, or "*name"
     // catch-alls to match on specific patterns. The Router takes some data with its `new` method
     // that can be shared throughout all routes. If you don't need any shared data, use `()`.
-    let router = Router::new();
+    let router = Router::with_data(resolver(&env)?);
 
     // Add as many routes as your Worker needs! Each route will get a `Request` for handling HTTP
     // functionality and a `RouteContext` which you can use to  and get route parameters and
     // Enviornment bindings like KV Stores, Durable Objects, Secrets, and Variables.
     router
         .get("/", index)
+        .get("/help", show_help)
+        .get("/suggest", suggest)
+        .get("/api/resolve", api_resolve)
+        .post_async("/api/resolve", api_resolve_batch)
+        .options("/api/resolve", api_preflight)
         .get("/osdf.xml", osdf)
         .run(req, env)
         .await
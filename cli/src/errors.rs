@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub(crate) enum Error {
+    #[error("IoError: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error determining redirect url: {0}")]
+    Url(#[from] search_shortcuts::errors::Error),
+    #[error("Error loading shortcuts: {0}")]
+    Registry(search_shortcuts::errors::Error),
+    #[error("Couldn't open the url: {0} exited with {1}")]
+    Open(&'static str, std::process::ExitStatus),
+    #[error("{0} of the config's examples failed")]
+    Validation(usize),
+    #[error("Found {0} problems with the config")]
+    Lint(usize),
+}
@@ -0,0 +1,243 @@
+//! A desktop launcher, either as a rofi script mode:
+//!
+//! ```sh
+//! rofi -show shortcuts -modi "shortcuts:search-shortcuts launcher"
+//! ```
+//!
+//! or driving any dmenu compatible menu:
+//!
+//! ```sh
+//! search-shortcuts launcher --dmenu "rofi -dmenu -i"
+//! ```
+//!
+//! Picking a keyword that takes arguments asks for them in a second menu, and
+//! anything typed instead of picked is resolved as a whole query. Leaving the
+//! second menu empty opens the keyword on its own if it works without
+//! arguments.
+
+use crate::errors::Result;
+use crate::open;
+
+use search_shortcuts::shortcut::Arguments;
+use search_shortcuts::Resolver;
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Separates rofi's row options from the row text.
+const ROFI_OPTION: char = '\0';
+const ROFI_FIELD: char = '\x1f';
+
+/// One row of the menu.
+#[derive(Debug, PartialEq, Eq)]
+struct Item {
+    line: String,
+    keyword: String,
+    arguments: Arguments,
+}
+
+impl Item {
+    fn takes_arguments(&self) -> bool {
+        self.arguments != Arguments::None
+    }
+
+    /// The query for the arguments typed into the second menu, or `None` if
+    /// the keyword needs arguments and none were given.
+    fn query(&self, arguments: &str) -> Option<String> {
+        match (self.arguments, arguments.trim()) {
+            (Arguments::Required, "") => None,
+            (_, "") => Some(self.keyword.clone()),
+            (_, arguments) => Some(format!("{} {}", self.keyword, arguments)),
+        }
+    }
+}
+
+fn items(resolver: &Resolver) -> Vec<Item> {
+    let keywords: Vec<_> = resolver
+        .shortcuts()
+        .map(|shortcut| shortcut.keywords().join(", "))
+        .collect();
+    let width = keywords.iter().map(String::len).max().unwrap_or_default();
+    resolver
+        .shortcuts()
+        .zip(keywords)
+        .map(|(shortcut, keywords)| Item {
+            line: format!(
+                "{:width$}  {}",
+                keywords,
+                shortcut.description(),
+                width = width
+            )
+            .trim_end()
+            .to_string(),
+            keyword: shortcut.keywords()[0].to_string(),
+            arguments: shortcut.arguments(),
+        })
+        .collect()
+}
+
+/// Resolves and opens a query.
+fn launch(resolver: &Resolver, query: &str) -> Result<()> {
+    open(resolver.resolve(query)?.url.as_str())
+}
+
+/// Runs as a rofi script mode, where rofi calls the launcher with no input
+/// to list the rows and again with each selection.
+///
+/// See `man rofi-script` for the protocol.
+pub(crate) fn rofi(resolver: &Resolver, input: &str) -> Result<()> {
+    let items = items(resolver);
+    // 0 is the first call, 1 a selected row and 2 custom input
+    let retv = env::var("ROFI_RETV").unwrap_or_default();
+    if retv == "0" || (retv.is_empty() && input.is_empty()) {
+        println!("{}prompt{}shortcuts", ROFI_OPTION, ROFI_FIELD);
+        for (i, item) in items.iter().enumerate() {
+            println!("{}{}info{}{}", item.line, ROFI_OPTION, ROFI_FIELD, i);
+        }
+        return Ok(());
+    }
+
+    // a keyword that was picked earlier and is waiting for its arguments
+    if let Ok(keyword) = env::var("ROFI_DATA") {
+        let query = items
+            .iter()
+            .find(|item| item.keyword == keyword)
+            .and_then(|item| item.query(input));
+        return match query {
+            Some(query) => launch(resolver, &query),
+            None => Ok(()),
+        };
+    }
+    let picked = env::var("ROFI_INFO")
+        .ok()
+        .and_then(|info| items.get(info.parse::<usize>().ok()?))
+        .or_else(|| items.iter().find(|item| item.line == input));
+    match picked {
+        Some(item) if item.takes_arguments() => {
+            // no rows, so whatever is typed next comes back as custom input
+            println!("{}prompt{}{}", ROFI_OPTION, ROFI_FIELD, item.keyword);
+            println!("{}data{}{}", ROFI_OPTION, ROFI_FIELD, item.keyword);
+            Ok(())
+        }
+        Some(item) => launch(resolver, &item.keyword),
+        None => launch(resolver, input),
+    }
+}
+
+/// Shows the rows in a dmenu compatible menu, returning the chosen or typed
+/// line, which is empty if nothing was typed, or `None` if the menu was
+/// dismissed.
+fn menu(command: &str, prompt: &str, rows: &[&str]) -> Result<Option<String>> {
+    let mut args = command.split_whitespace();
+    let program = args.next().unwrap_or("dmenu");
+    let mut child = Command::new(program)
+        .args(args)
+        .args(["-p", prompt])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        for row in rows {
+            writeln!(stdin, "{}", row)?;
+        }
+    }
+    let output = child.wait_with_output()?;
+    // dmenu exits with 1 when dismissed
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+pub(crate) fn dmenu(resolver: &Resolver, command: &str) -> Result<()> {
+    let items = items(resolver);
+    let rows: Vec<_> = items.iter().map(|item| item.line.as_str()).collect();
+    let line = match menu(command, "shortcuts", &rows)? {
+        Some(line) if !line.is_empty() => line,
+        _ => return Ok(()),
+    };
+    match items.iter().find(|item| item.line == line) {
+        Some(item) if item.takes_arguments() => {
+            let query =
+                menu(command, &item.keyword, &[])?.and_then(|arguments| item.query(&arguments));
+            match query {
+                Some(query) => launch(resolver, &query),
+                None => Ok(()),
+            }
+        }
+        Some(item) => launch(resolver, &item.keyword),
+        None => launch(resolver, &line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use search_shortcuts::Registry;
+    use test_case::test_case;
+
+    fn resolver() -> Resolver {
+        let config = r#"
+            [[redirect]]
+            keyword = "wiki"
+            description = "Team wiki."
+            url = "https://wiki.example.com/"
+
+            [[shortcut]]
+            keyword = "jira"
+            aliases = ["j"]
+            template = "https://jira.example.com/browse/{1|path}"
+
+            [[shortcut]]
+            keyword = "x"
+            description = "xkcd."
+            template = "https://xkcd.com/{1|path}/"
+            url = "https://xkcd.com/"
+        "#;
+        Resolver::builder()
+            .registry(Registry::from_toml(config).unwrap())
+            .build()
+    }
+
+    fn item(line: &str, keyword: &str, arguments: Arguments) -> Item {
+        Item {
+            line: line.to_string(),
+            keyword: keyword.to_string(),
+            arguments,
+        }
+    }
+
+    #[test]
+    fn menu_items() {
+        assert_eq!(
+            vec![
+                item("wiki     Team wiki.", "wiki", Arguments::None),
+                item("jira, j", "jira", Arguments::Required),
+                item("x        xkcd.", "x", Arguments::Optional),
+            ],
+            items(&resolver())
+        );
+    }
+
+    #[test_case(Arguments::Required, "927", Some("x 927"))]
+    #[test_case(Arguments::Required, " ", None ; "required but empty")]
+    #[test_case(Arguments::Optional, "927", Some("x 927") ; "optional")]
+    #[test_case(Arguments::Optional, "", Some("x") ; "optional and empty")]
+    #[test_case(Arguments::None, "", Some("x") ; "none")]
+    fn item_query(arguments: Arguments, typed: &str, expected: Option<&str>) {
+        let item = item("x", "x", arguments);
+        assert_eq!(expected.map(str::to_string), item.query(typed));
+    }
+
+    #[test]
+    fn optional_arguments_resolve() {
+        let resolver = resolver();
+        let item = items(&resolver).pop().unwrap();
+        for (typed, expected) in [("", "https://xkcd.com/"), ("927", "https://xkcd.com/927/")] {
+            let query = item.query(typed).unwrap();
+            assert_eq!(expected, resolver.resolve(&query).unwrap().url.as_str());
+        }
+    }
+}
@@ -0,0 +1,290 @@
+mod errors;
+mod launcher;
+use errors::{Error, Result};
+
+use clap::{Parser, Subcommand};
+use search_shortcuts::shortcut::Arguments;
+use search_shortcuts::{help, lint, BangPack, Registry, Resolver};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+
+/// Resolves search shortcuts from the terminal.
+#[derive(Debug, Parser)]
+#[command(name = "search-shortcuts", version)]
+struct Cli {
+    /// A TOML or JSON shortcuts config to use instead of the default one.
+    #[arg(long, global = true, env = "SHORTCUTS_FILE")]
+    config: Option<PathBuf>,
+    /// A local copy of DuckDuckGo's bang database.
+    #[arg(long, global = true, env = "BANGS_FILE")]
+    bangs: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Cmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum Cmd {
+    /// Prints the url a query goes to.
+    Resolve {
+        #[arg(required = true)]
+        query: Vec<String>,
+    },
+    /// Opens the url a query goes to in the default browser.
+    Open {
+        #[arg(required = true)]
+        query: Vec<String>,
+    },
+    /// Lists every keyword.
+    List,
+    /// Loads a config and checks that each of its examples resolves using the
+    /// shortcut it belongs to.
+    Validate { config: PathBuf },
+    /// Loads a config and reports duplicate keywords and shortcuts that are
+    /// hidden by earlier ones.
+    Lint { config: PathBuf },
+    /// Acts as a rofi script mode, or runs a dmenu compatible menu with
+    /// `--dmenu`, to pick a keyword and open where it goes.
+    Launcher {
+        /// The menu command to run, eg `dmenu` or `rofi -dmenu -i`.
+        #[arg(long, value_name = "COMMAND")]
+        dmenu: Option<String>,
+        /// The row or text rofi passes back.
+        input: Vec<String>,
+    },
+}
+
+#[cfg(target_os = "macos")]
+const OPENER: &str = "open";
+#[cfg(not(target_os = "macos"))]
+const OPENER: &str = "xdg-open";
+
+fn load_registry(config: Option<&Path>, bangs: Option<&Path>) -> Result<Registry> {
+    let mut registry = match config {
+        Some(config) => Registry::from_path(config).map_err(Error::Registry)?,
+        None => Registry::default(),
+    };
+    if let Some(bangs) = bangs {
+        registry.add_bangs(BangPack::from_path(bangs).map_err(Error::Registry)?);
+    }
+    Ok(registry)
+}
+
+fn open(url: &str) -> Result<()> {
+    // the browser may inherit the opener's stdout, which would keep a
+    // launcher's menu waiting for it to close
+    let status = Command::new(OPENER)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(Error::Open(OPENER, status));
+    }
+    Ok(())
+}
+
+fn list(resolver: &Resolver) {
+    let mut entries = help::entries(resolver);
+    entries.extend(help::pattern_entries(resolver));
+    let keywords: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            std::iter::once(entry.keyword)
+                .chain(entry.aliases.iter().copied())
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .collect();
+    let width = keywords.iter().map(String::len).max().unwrap_or_default();
+    for (keywords, entry) in keywords.iter().zip(&entries) {
+        println!("{:width$}  {}", keywords, entry.description, width = width);
+    }
+}
+
+/// Resolves an example and writes the outcome to `out`, returning whether it
+/// went to the shortcut or pattern it belongs to, one of whose keywords or
+/// name is `expected`.
+fn check_example(
+    out: &mut impl Write,
+    resolver: &Resolver,
+    example: &str,
+    expected: &[&str],
+) -> io::Result<bool> {
+    match resolver.resolve(example) {
+        Ok(resolution)
+            if resolution
+                .shortcut
+                .as_deref()
+                .is_some_and(|keyword| expected.contains(&keyword)) =>
+        {
+            writeln!(out, "ok    {:?} -> {}", example, resolution.url)?;
+            Ok(true)
+        }
+        Ok(resolution) => {
+            writeln!(
+                out,
+                "FAIL  {:?} -> {} (resolved by {} instead of {})",
+                example,
+                resolution.url,
+                resolution.shortcut.as_deref().unwrap_or("no shortcut"),
+                expected[0]
+            )?;
+            Ok(false)
+        }
+        Err(err) => {
+            writeln!(out, "FAIL  {:?}: {}", example, err)?;
+            Ok(false)
+        }
+    }
+}
+
+/// Resolves every example in the config, returning how many failed.
+fn validate(out: &mut impl Write, resolver: &Resolver) -> io::Result<usize> {
+    let mut failures = 0;
+    for shortcut in resolver.shortcuts() {
+        let keywords = shortcut.keywords();
+        let mut examples = shortcut.examples();
+        if examples.is_empty() && shortcut.arguments() != Arguments::Required {
+            examples.push(keywords[0]);
+        }
+        for example in examples {
+            if !check_example(out, resolver, example, &keywords)? {
+                failures += 1;
+            }
+        }
+    }
+    for pattern in resolver.patterns() {
+        for example in pattern.examples() {
+            if !check_example(out, resolver, example, &[pattern.name()])? {
+                failures += 1;
+            }
+        }
+    }
+    Ok(failures)
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let config = match &cli.command {
+        Cmd::Validate { config } | Cmd::Lint { config } => Some(config.as_path()),
+        _ => cli.config.as_deref(),
+    };
+    let resolver = Resolver::builder()
+        .registry(load_registry(config, cli.bangs.as_deref())?)
+        .build();
+    match cli.command {
+        Cmd::Resolve { query } => println!("{}", resolver.resolve(&query.join(" "))?.url),
+        Cmd::Open { query } => open(resolver.resolve(&query.join(" "))?.url.as_str())?,
+        Cmd::List => list(&resolver),
+        Cmd::Launcher {
+            dmenu: Some(command),
+            ..
+        } => launcher::dmenu(&resolver, &command)?,
+        Cmd::Launcher { dmenu: None, input } => launcher::rofi(&resolver, &input.join(" "))?,
+        Cmd::Validate { .. } => match validate(&mut io::stdout().lock(), &resolver)? {
+            0 => {}
+            failures => return Err(Error::Validation(failures)),
+        },
+        Cmd::Lint { .. } => {
+            let lints = lint::lint(&resolver);
+            for lint in &lints {
+                println!("{}", lint);
+            }
+            if !lints.is_empty() {
+                return Err(Error::Lint(lints.len()));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const CONFIG: &str = r#"
+        [[redirect]]
+        keyword = "wiki"
+        url = "https://wiki.example.com/"
+
+        [[shortcut]]
+        keyword = "jira"
+        examples = ["jira ABC-123"]
+        template = "https://jira.example.com/browse/{1|path}"
+
+        [[shortcut]]
+        keyword = "two"
+        template = "https://example.com/{1}/{2}"
+    "#;
+
+    fn resolver(config: &str) -> Resolver {
+        Resolver::builder()
+            .registry(Registry::from_toml(config).unwrap())
+            .build()
+    }
+
+    #[test_case("jira ABC-123", &["jira"], true, "ok    \"jira ABC-123\" -> https://jira.example.com/browse/ABC-123\n" ; "resolves")]
+    #[test_case("wiki", &["jira"], false, "FAIL  \"wiki\" -> https://wiki.example.com/ (resolved by wiki instead of jira)\n" ; "other shortcut")]
+    #[test_case("lol donkey", &["jira"], false, "FAIL  \"lol donkey\" -> https://duckduckgo.com/?k1=-1&q=lol+donkey (resolved by no shortcut instead of jira)\n" ; "fallback")]
+    #[test_case("two one", &["two"], false, "FAIL  \"two one\": Missing argument: 2\n" ; "error")]
+    fn check_examples(example: &str, expected: &[&str], ok: bool, output: &str) -> Result<()> {
+        let mut out = Vec::new();
+        assert_eq!(
+            ok,
+            check_example(&mut out, &resolver(CONFIG), example, expected)?
+        );
+        assert_eq!(output, String::from_utf8_lossy(&out));
+        Ok(())
+    }
+
+    #[test_case(CONFIG, 0 ; "valid")]
+    #[test_case(r#"
+        [[shortcut]]
+        keyword = "jira"
+        examples = ["jira ABC-123", "ABC-123"]
+        template = "https://jira.example.com/browse/{1|path}"
+    "#, 1 ; "example that searches")]
+    fn validate_failures(config: &str, expected: usize) -> Result<()> {
+        let mut out = Vec::new();
+        assert_eq!(expected, validate(&mut out, &resolver(config))?);
+        Ok(())
+    }
+
+    fn validate_config(config: &str) -> Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "search-shortcuts-validate-{}-{}.toml",
+            std::process::id(),
+            config.len()
+        ));
+        std::fs::write(&path, config)?;
+        let cli = Cli::try_parse_from(["search-shortcuts", "validate", path.to_str().unwrap()]);
+        let result = run(cli.unwrap());
+        std::fs::remove_file(&path)?;
+        result
+    }
+
+    #[test]
+    fn validate_exit_status() {
+        assert!(validate_config(CONFIG).is_ok());
+        let invalid = CONFIG.replace("jira ABC-123", "ABC-123");
+        assert!(matches!(
+            validate_config(&invalid),
+            Err(Error::Validation(1))
+        ));
+        assert!(matches!(
+            validate_config("[[shortcut]]"),
+            Err(Error::Registry(_))
+        ));
+    }
+}
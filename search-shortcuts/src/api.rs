@@ -0,0 +1,175 @@
+//! The JSON resolve API served by both front ends at `/api/resolve`, for
+//! scripts and browser extensions that want the url without following a
+//! redirect.
+//!
+//! `GET /api/resolve?q=gh rust` returns one result and `POST /api/resolve`
+//! with `{"queries": ["gh rust", "twir"]}` returns a list of results in the
+//! same order.
+
+use crate::resolver::Resolver;
+use crate::shortcut::Resolution;
+
+use serde::{Deserialize, Serialize};
+
+/// The most queries accepted in one batch request.
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// Lets the API be called from any origin, eg a browser extension.
+pub const CORS_HEADERS: &[(&str, &str)] = &[
+    ("Access-Control-Allow-Origin", "*"),
+    ("Access-Control-Allow-Methods", "GET, POST, OPTIONS"),
+    ("Access-Control-Allow-Headers", "Content-Type"),
+    ("Access-Control-Max-Age", "86400"),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchRequest {
+    pub queries: Vec<String>,
+}
+
+/// The body of a request that couldn't be handled at all.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiError {
+    pub error: String,
+}
+
+impl ApiError {
+    pub fn new(error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+        }
+    }
+}
+
+/// The result for one query, which serializes as
+/// `{"query", "url", "shortcut", "arguments", "kind"}`, plus `did_you_mean`
+/// for a search starting with a mistyped keyword, or `{"query", "error"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum ApiResult {
+    Resolved {
+        query: String,
+        #[serde(flatten)]
+        resolution: Resolution,
+    },
+    Failed {
+        query: String,
+        error: String,
+    },
+}
+
+impl ApiResult {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, ApiResult::Resolved { .. })
+    }
+}
+
+pub fn resolve(resolver: &Resolver, query: &str) -> ApiResult {
+    match resolver.resolve(query) {
+        Ok(resolution) => ApiResult::Resolved {
+            query: query.to_string(),
+            resolution,
+        },
+        Err(err) => ApiResult::Failed {
+            query: query.to_string(),
+            error: err.to_string(),
+        },
+    }
+}
+
+/// Resolves every query in the batch, or returns an error if the batch is
+/// too big.
+pub fn resolve_batch(
+    resolver: &Resolver,
+    batch: &BatchRequest,
+) -> Result<Vec<ApiResult>, ApiError> {
+    if batch.queries.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::new(format!(
+            "at most {} queries can be resolved at once",
+            MAX_BATCH_SIZE
+        )));
+    }
+    Ok(batch
+        .queries
+        .iter()
+        .map(|query| resolve(resolver, query))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Result;
+    use serde_json::json;
+
+    #[test]
+    fn resolve_json() -> Result<()> {
+        let resolver = Resolver::default();
+        assert_eq!(
+            json!({
+                "query": "gh rust-lang/rust #1",
+                "url": "https://github.com/rust-lang/rust/issues/1",
+                "shortcut": "gh",
+                "arguments": ["rust-lang/rust", "#1"],
+                "kind": "keyword",
+            }),
+            serde_json::to_value(resolve(&resolver, "gh rust-lang/rust #1"))?
+        );
+        assert_eq!(
+            json!({
+                "query": "cats",
+                "url": "https://duckduckgo.com/?k1=-1&q=cats",
+                "shortcut": null,
+                "arguments": [],
+                "kind": "fallback",
+            }),
+            serde_json::to_value(resolve(&resolver, "cats"))?
+        );
+        assert_eq!(
+            json!({
+                "query": "crate and barrel",
+                "url": "https://duckduckgo.com/?k1=-1&q=crate+and+barrel",
+                "shortcut": null,
+                "arguments": [],
+                "kind": "fallback",
+                "did_you_mean": ["crates and barrel"],
+            }),
+            serde_json::to_value(resolve(&resolver, "crate and barrel"))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn batch() -> Result<()> {
+        let resolver = Resolver::builder()
+            .registry(crate::Registry::from_toml(
+                r#"
+                [[shortcut]]
+                keyword = "two"
+                template = "https://example.com/{1}/{2}"
+                "#,
+            )?)
+            .build();
+        let batch: BatchRequest = serde_json::from_str(r#"{"queries": ["two a b", "two a"]}"#)?;
+        let results = resolve_batch(&resolver, &batch).unwrap();
+        assert_eq!(
+            json!([
+                {
+                    "query": "two a b",
+                    "url": "https://example.com/a/b",
+                    "shortcut": "two",
+                    "arguments": ["a", "b"],
+                    "kind": "keyword",
+                },
+                {"query": "two a", "error": "Missing argument: 2"},
+            ]),
+            serde_json::to_value(&results)?
+        );
+
+        let batch = BatchRequest {
+            queries: vec!["x".to_string(); MAX_BATCH_SIZE + 1],
+        };
+        assert!(resolve_batch(&resolver, &batch).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+//! Imports DuckDuckGo's bang database, the `bang.js` JSON file that lists
+//! every bang as `{"t": trigger, "s": site name, "u": url, "d": domain}`.
+//!
+//! Bangs are only used through the `!bang` syntax and only when no shortcut
+//! has the same keyword, so they sit beneath the hand-written shortcuts.
+
+use crate::errors::Result;
+use crate::query::ParsedQuery;
+use crate::template::Template;
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use url::Url;
+
+/// The placeholder DuckDuckGo uses for the search terms.
+const SEARCH_TERMS: &str = "{{{s}}}";
+
+#[derive(Debug, Deserialize)]
+struct RawBang {
+    t: String,
+    s: String,
+    u: String,
+    #[serde(default)]
+    d: String,
+}
+
+#[derive(Debug)]
+struct Bang {
+    site: String,
+    /// Where the bang goes without any search terms.
+    home: Option<Url>,
+    template: Option<Template>,
+}
+
+impl Bang {
+    /// Returns `None` for bangs that can't be used outside of DuckDuckGo, eg
+    /// ones with relative urls.
+    fn from_raw(raw: &RawBang) -> Option<Self> {
+        if !raw.u.contains(SEARCH_TERMS) {
+            return Some(Self {
+                site: raw.s.clone(),
+                home: Some(Url::parse(&raw.u).ok()?),
+                template: None,
+            });
+        }
+        let home = match raw.d.as_str() {
+            "" => None,
+            domain => Url::parse(&format!("https://{}/", domain)).ok(),
+        };
+        Some(Self {
+            site: raw.s.clone(),
+            home,
+            template: Some(Template::from_markers(&raw.u, &[(SEARCH_TERMS, None)]).ok()?),
+        })
+    }
+
+    fn resolve(&self, keyword: &str, rest: &str) -> Option<Result<Url>> {
+        match (&self.template, &self.home) {
+            (_, Some(home)) if rest.is_empty() => Some(Ok(home.clone())),
+            (Some(template), _) => Some(template.render(&ParsedQuery::new(keyword, rest))),
+            (None, Some(home)) => Some(Ok(home.clone())),
+            (None, None) => None,
+        }
+    }
+}
+
+/// A bang that can't be reached because a shortcut has the same keyword.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub keyword: String,
+    pub site: String,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "!{} ({}) is shadowed by a shortcut with the same keyword",
+            self.keyword, self.site
+        )
+    }
+}
+
+/// A set of bangs loaded from a local copy of DuckDuckGo's bang database.
+#[derive(Debug, Default)]
+pub struct BangPack {
+    bangs: HashMap<String, Bang>,
+    skipped: usize,
+}
+
+impl BangPack {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let raw: Vec<RawBang> = serde_json::from_str(json)?;
+        let mut pack = Self::default();
+        for raw in &raw {
+            match Bang::from_raw(raw) {
+                Some(bang) => {
+                    // the database is sorted by popularity so keep the first
+                    pack.bangs.entry(raw.t.to_lowercase()).or_insert(bang);
+                }
+                None => pack.skipped += 1,
+            }
+        }
+        Ok(pack)
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bangs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bangs.is_empty()
+    }
+
+    /// The number of bangs that couldn't be imported.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Adds the bangs from `other` that aren't already in this pack.
+    pub fn extend(&mut self, other: BangPack) {
+        for (keyword, bang) in other.bangs {
+            self.bangs.entry(keyword).or_insert(bang);
+        }
+        self.skipped += other.skipped;
+    }
+
+    /// Lists the bangs that share a keyword with one of `keywords`.
+    pub fn conflicts<'a>(&self, keywords: impl IntoIterator<Item = &'a str>) -> Vec<Conflict> {
+        let mut conflicts: Vec<_> = keywords
+            .into_iter()
+            .filter_map(|keyword| {
+                self.bangs
+                    .get(&keyword.to_lowercase())
+                    .map(|bang| Conflict {
+                        keyword: keyword.to_string(),
+                        site: bang.site.clone(),
+                    })
+            })
+            .collect();
+        conflicts.sort_by(|a, b| a.keyword.cmp(&b.keyword));
+        conflicts.dedup();
+        conflicts
+    }
+
+    /// Resolves `!keyword rest`, returning `None` for unknown bangs.
+    pub fn resolve(&self, keyword: &str, rest: &str) -> Option<Result<Url>> {
+        self.bangs
+            .get(&keyword.to_lowercase())?
+            .resolve(keyword, rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const BANGS: &str = r#"[
+        {"c": "Tech", "d": "github.com", "r": 1, "s": "GitHub", "sc": "Programming", "t": "gh", "u": "https://github.com/search?q={{{s}}}"},
+        {"c": "Online Services", "d": "www.youtube.com", "r": 1, "s": "YouTube", "sc": "Video", "t": "yt", "u": "https://www.youtube.com/results?search_query={{{s}}}"},
+        {"c": "Research", "d": "en.wikipedia.org", "r": 1, "s": "Wikipedia", "sc": "Reference", "t": "wen", "u": "https://en.wikipedia.org/wiki/{{{s}}}"},
+        {"c": "Tech", "d": "example.com", "r": 0, "s": "Example", "sc": "Tools", "t": "ex", "u": "https://example.com/#/find/{{{s}}}"},
+        {"c": "Tech", "d": "crates.io", "r": 0, "s": "crates.io home", "sc": "Tools", "t": "cratesio", "u": "https://crates.io/"},
+        {"c": "Online Services", "d": "duckduckgo.com", "r": 0, "s": "DuckDuckGo Images", "sc": "Search", "t": "i", "u": "/?q={{{s}}}&iax=images&ia=images"}
+    ]"#;
+
+    #[test_case(
+        "yt",
+        "rust async",
+        Some("https://www.youtube.com/results?search_query=rust+async")
+    )]
+    #[test_case("YT", "cats", Some("https://www.youtube.com/results?search_query=cats") ; "case insensitive")]
+    #[test_case("yt", "", Some("https://www.youtube.com/") ; "bare bang")]
+    #[test_case(
+        "wen",
+        "Rust (language)",
+        Some("https://en.wikipedia.org/wiki/Rust%20(language)")
+    )]
+    #[test_case("ex", "a#b", Some("https://example.com/#/find/a%23b"))]
+    #[test_case("cratesio", "ignored", Some("https://crates.io/"))]
+    #[test_case("i", "cats", None ; "relative url skipped")]
+    #[test_case("nope", "cats", None ; "unknown")]
+    fn resolve(keyword: &str, rest: &str, expected: Option<&str>) -> Result<()> {
+        let pack = BangPack::from_json(BANGS)?;
+        let actual = pack.resolve(keyword, rest).transpose()?;
+        assert_eq!(expected, actual.as_ref().map(Url::as_str));
+        Ok(())
+    }
+
+    #[test]
+    fn skipped_and_conflicts() -> Result<()> {
+        let pack = BangPack::from_json(BANGS)?;
+        assert_eq!(5, pack.len());
+        assert_eq!(1, pack.skipped());
+        let conflicts = pack.conflicts(["gh", "docs", "yt"]);
+        let keywords: Vec<_> = conflicts.iter().map(|c| c.keyword.as_str()).collect();
+        assert_eq!(vec!["gh", "yt"], keywords);
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+//! Recognising queries that are already somewhere to go: urls, domains, IP
+//! addresses and internal hosts, eg `http://localhost:8080/x`,
+//! `example.com/path?x=1` or `[::1]:3000`.
+//!
+//! Public domains need a suffix on the public suffix list and go over https.
+//! IP addresses, `localhost` and hosts under one of the internal TLDs go over
+//! http as they rarely have certificates. Queries with spaces are only
+//! checked for a domain split up by mobile autocomplete, eg
+//! `www.example. com`.
+
+use crate::errors::{Error, Result};
+
+use psl::{List, Psl};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use url::Url;
+
+/// The TLDs used for internal hosts unless the config sets `internal_tlds`.
+pub const DEFAULT_INTERNAL_TLDS: &[&str] = &["home.arpa", "internal", "lan", "local"];
+
+/// Public suffixes that are also common file extensions, eg `main.rs`, which
+/// are only treated as domains with a `www.` or a path.
+const FILE_EXTENSIONS: &[&str] = &[
+    "ac", "am", "md", "ml", "mov", "pl", "ps", "py", "rs", "sh", "so", "zip",
+];
+
+/// Why a query was recognised as a destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// An http or https url, eg `https://example.com/`.
+    Url,
+    /// A domain with a public suffix, eg `example.com/path`.
+    Domain,
+    /// An IPv4 or bracketed IPv6 address, eg `192.168.1.1`.
+    IpAddress,
+    /// `localhost` or a host under an internal TLD, eg `router.lan`.
+    Internal,
+    /// A domain split up by mobile autocomplete, eg `www.example. com`.
+    Autocomplete,
+}
+
+impl Kind {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Kind::Url => "the query is an http or https url",
+            Kind::Domain => "the query is a domain with a known suffix",
+            Kind::IpAddress => "the query is an IP address",
+            Kind::Internal => "the query is localhost or a host under an internal TLD",
+            Kind::Autocomplete => "without its spaces the query is a domain with a known suffix",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Destination {
+    pub url: Url,
+    pub kind: Kind,
+}
+
+/// Works out where the query goes if it's a url, domain or address.
+pub fn detect(query: &str, internal_tlds: &[String]) -> Result<Option<Destination>> {
+    let query = query.trim();
+    if query.contains(char::is_whitespace) {
+        return autocomplete(query);
+    }
+    let lower = query.to_lowercase();
+    if lower.starts_with("http://") || lower.starts_with("https://") {
+        return Ok(Url::parse(query)
+            .ok()
+            .filter(|url| url.host().is_some())
+            .map(|url| Destination {
+                url,
+                kind: Kind::Url,
+            }));
+    }
+
+    let end = query.find(['/', '?', '#']).unwrap_or(query.len());
+    let (authority, rest) = query.split_at(end);
+    // `user@example.com` is more likely an email address than a login
+    if authority.contains('@') {
+        return Ok(None);
+    }
+    let (host, port) = match split_port(authority) {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+    let host = host.to_lowercase();
+    let kind = if is_ip_address(&host) {
+        Kind::IpAddress
+    } else if is_internal(&host, internal_tlds) {
+        Kind::Internal
+    } else if is_public_domain(&host, port.is_some() || !rest.is_empty()) {
+        Kind::Domain
+    } else {
+        return Ok(None);
+    };
+    let scheme = match kind {
+        Kind::Domain => "https",
+        _ => "http",
+    };
+    Ok(Url::parse(&format!("{}://{}", scheme, query))
+        .ok()
+        .map(|url| Destination { url, kind }))
+}
+
+/// Handles domains split up by mobile autocomplete, eg `www.example. com`.
+/// Each space has to be next to a dot so that several words aren't joined
+/// into a domain, eg `what is example.com`.
+fn autocomplete(query: &str) -> Result<Option<Destination>> {
+    let words: Vec<_> = query.split_whitespace().collect();
+    let split_at_dots = words
+        .windows(2)
+        .all(|pair| pair[0].ends_with('.') || pair[1].starts_with('.'));
+    if !split_at_dots {
+        return Ok(None);
+    }
+    let domain = words.concat();
+    let known = List
+        .domain(domain.to_lowercase().as_bytes())
+        .is_some_and(|d| d.suffix().is_known());
+    if !known {
+        return Ok(None);
+    }
+    let url =
+        Url::parse(&format!("https://{}", domain)).map_err(|_| Error::InvalidDomain(domain))?;
+    Ok(Some(Destination {
+        url,
+        kind: Kind::Autocomplete,
+    }))
+}
+
+/// Splits `host:port`, returning `None` if the port isn't a valid port.
+fn split_port(authority: &str) -> Option<(&str, Option<u16>)> {
+    let host_end = match authority.strip_prefix('[') {
+        Some(ipv6) => ipv6.find(']')? + 2,
+        None => authority.find(':').unwrap_or(authority.len()),
+    };
+    let (host, port) = authority.split_at(host_end);
+    match port.strip_prefix(':') {
+        Some(port) => Some((host, Some(port.parse().ok()?))),
+        None if port.is_empty() => Some((host, None)),
+        None => None,
+    }
+}
+
+fn is_ip_address(host: &str) -> bool {
+    match host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        Some(ipv6) => ipv6.parse::<Ipv6Addr>().is_ok(),
+        None => host.parse::<Ipv4Addr>().is_ok(),
+    }
+}
+
+fn is_internal(host: &str, internal_tlds: &[String]) -> bool {
+    let is_under = |tld: &str| {
+        host.strip_suffix(tld)
+            .and_then(|name| name.strip_suffix('.'))
+            .is_some_and(is_hostname)
+    };
+    host == "localhost"
+        || is_under("localhost")
+        || internal_tlds
+            .iter()
+            .any(|tld| is_under(&tld.to_lowercase()))
+}
+
+/// Whether each label of the name is made of letters, digits and hyphens,
+/// allowing any non-ASCII letters of internationalised domains.
+fn is_hostname(name: &str) -> bool {
+    name.split('.').all(|label| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+    })
+}
+
+/// Whether the host is a domain on the public suffix list. Internationalised
+/// domains are checked as they are and converted to punycode by the url.
+fn is_public_domain(host: &str, has_path_or_port: bool) -> bool {
+    if !is_hostname(host) {
+        return false;
+    }
+    let suffix = match List.suffix(host.as_bytes()) {
+        Some(suffix) if suffix.is_known() && suffix.as_bytes() != host.as_bytes() => suffix,
+        _ => return false,
+    };
+    let suffix = std::str::from_utf8(suffix.as_bytes()).unwrap_or_default();
+    has_path_or_port || host.starts_with("www.") || !FILE_EXTENSIONS.contains(&suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn internal_tlds() -> Vec<String> {
+        DEFAULT_INTERNAL_TLDS
+            .iter()
+            .map(|tld| tld.to_string())
+            .collect()
+    }
+
+    #[test_case("https://example.com/a?b#c", "https://example.com/a?b#c", Kind::Url)]
+    #[test_case("HTTP://localhost:8080/x", "http://localhost:8080/x", Kind::Url ; "scheme case")]
+    #[test_case("www.example.com", "https://www.example.com/", Kind::Domain)]
+    #[test_case("example.com/path?x=1", "https://example.com/path?x=1", Kind::Domain)]
+    #[test_case("Example.COM:8443", "https://example.com:8443/", Kind::Domain ; "port")]
+    #[test_case("crates.io", "https://crates.io/", Kind::Domain)]
+    #[test_case("bücher.de", "https://xn--bcher-kva.de/", Kind::Domain ; "idn")]
+    #[test_case("docs.rs/tokio", "https://docs.rs/tokio", Kind::Domain ; "file extension suffix with path")]
+    #[test_case("192.168.1.1", "http://192.168.1.1/", Kind::IpAddress)]
+    #[test_case("10.0.0.1:8080/admin", "http://10.0.0.1:8080/admin", Kind::IpAddress)]
+    #[test_case("[::1]:3000", "http://[::1]:3000/", Kind::IpAddress)]
+    #[test_case("localhost:8080/x", "http://localhost:8080/x", Kind::Internal)]
+    #[test_case("app.localhost", "http://app.localhost/", Kind::Internal)]
+    #[test_case("router.lan", "http://router.lan/", Kind::Internal)]
+    #[test_case("printer.home.arpa", "http://printer.home.arpa/", Kind::Internal)]
+    #[test_case("www.example. com", "https://www.example.com/", Kind::Autocomplete)]
+    #[test_case("docs .rs", "https://docs.rs/", Kind::Autocomplete ; "space before the dot")]
+    fn destinations(query: &str, expected: &str, kind: Kind) -> Result<()> {
+        let destination = detect(query, &internal_tlds())?.expect("a destination");
+        assert_eq!(expected, destination.url.as_str());
+        assert_eq!(kind, destination.kind);
+        Ok(())
+    }
+
+    #[test_case("search" ; "word")]
+    #[test_case("main.rs" ; "file name")]
+    #[test_case("node.js" ; "unknown suffix")]
+    #[test_case("802.11p" ; "standard")]
+    #[test_case("1.2.3" ; "version")]
+    #[test_case("user@example.com" ; "email")]
+    #[test_case("example.com:http" ; "invalid port")]
+    #[test_case("ftp://example.com/" ; "other scheme")]
+    #[test_case("std::fs" ; "rust path")]
+    #[test_case("e.g." ; "abbreviation")]
+    #[test_case("-example.com" ; "invalid label")]
+    #[test_case("router.corp" ; "not an internal tld by default")]
+    #[test_case("lol donkey" ; "words")]
+    #[test_case("what is example.com" ; "words before a domain")]
+    #[test_case("hello world.com" ; "words joined into a domain")]
+    #[test_case("example. com is down" ; "words after a domain")]
+    fn not_destinations(query: &str) -> Result<()> {
+        assert_eq!(None, detect(query, &internal_tlds())?);
+        Ok(())
+    }
+
+    #[test]
+    fn configured_internal_tlds() -> Result<()> {
+        let destination = detect("wiki.corp/page", &["corp".to_string()])?;
+        assert_eq!(
+            Some("http://wiki.corp/page"),
+            destination.as_ref().map(|d| d.url.as_str())
+        );
+        Ok(())
+    }
+}
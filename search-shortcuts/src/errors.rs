@@ -6,4 +6,68 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 pub enum Error {
     #[error("Error parsing url: {0}")]
     UrlParseError(#[from] url::ParseError),
+    #[error("Error reading config: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Error parsing TOML config: {0}")]
+    TomlParseError(#[from] toml::de::Error),
+    #[error("Error writing TOML config: {0}")]
+    TomlSerializeError(#[from] toml::ser::Error),
+    #[error("Error parsing JSON config: {0}")]
+    JsonParseError(#[from] serde_json::Error),
+    #[cfg(feature = "chrome")]
+    #[error("Error reading Chrome database: {0}")]
+    SqliteError(#[from] rusqlite::Error),
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid url template: {0}")]
+    InvalidTemplate(String),
+    #[error("Missing argument: {0}")]
+    MissingArgument(String),
+    #[error("Invalid argument {argument:?}: {reason}")]
+    InvalidArgument { argument: String, reason: String },
+    #[error("Invalid domain: {0}")]
+    InvalidDomain(String),
+    /// The query looks like a mistyped shortcut, with the corrected queries.
+    #[error("Did you mean {}?", quoted(.0))]
+    DidYouMean(Vec<String>),
+}
+
+fn quoted(queries: &[String]) -> String {
+    queries
+        .iter()
+        .map(|query| format!("`{}`", query))
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+impl Error {
+    pub(crate) fn invalid_argument(argument: &str, reason: &str) -> Self {
+        Error::InvalidArgument {
+            argument: argument.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Whether the error is down to the query rather than the config or
+    /// environment, ie a 400 rather than a 500.
+    pub fn is_user_error(&self) -> bool {
+        matches!(
+            self,
+            Error::UrlParseError(_)
+                | Error::MissingArgument(_)
+                | Error::InvalidArgument { .. }
+                | Error::InvalidDomain(_)
+                | Error::DidYouMean(_)
+        )
+    }
+
+    /// The HTTP status code to respond with.
+    pub fn status(&self) -> u16 {
+        match self {
+            // Multiple Choices: the corrected queries or the search as typed
+            Error::DidYouMean(_) => 300,
+            err if err.is_user_error() => 400,
+            _ => 500,
+        }
+    }
 }
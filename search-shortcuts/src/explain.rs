@@ -0,0 +1,165 @@
+//! A trace of how a query was resolved, for working out why a query went
+//! somewhere unexpected. See [`Resolver::explain`](crate::Resolver::explain).
+
+use crate::html::{escape, HEADER};
+
+use serde::Serialize;
+use std::fmt;
+use std::fmt::Write;
+use url::Url;
+
+/// The rules the resolver tries, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// A keyword on its own, eg `twir`.
+    BareKeyword,
+    /// A keyword followed by a separator and a query, eg `gh rust`.
+    Keyword,
+    /// A `!bang` anywhere in the query, eg `rust !gh`.
+    Bang,
+    /// A pasted DOI, arXiv id, PubMed id or ISBN, eg `2310.06825`.
+    Identifier,
+    /// A url, domain or IP address to open directly, eg `example.com/path`,
+    /// including a domain split up by mobile autocomplete.
+    Domain,
+    /// A query matching a pattern's regex, eg `E0382`. Depending on their
+    /// priority patterns are tried first, before the domain or last.
+    Pattern,
+    /// A mistyped keyword, eg `dcos tokio`.
+    Typo,
+    /// The fallback search engine.
+    Fallback,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Rule::BareKeyword => "bare keyword",
+            Rule::Keyword => "keyword",
+            Rule::Bang => "bang",
+            Rule::Domain => "domain",
+            Rule::Pattern => "pattern",
+            Rule::Identifier => "identifier",
+            Rule::Typo => "typo",
+            Rule::Fallback => "fallback",
+        })
+    }
+}
+
+/// One rule that was tried.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Step {
+    pub rule: Rule,
+    pub matched: bool,
+    /// The keyword the rule matched or tried.
+    pub keyword: Option<String>,
+    /// The positional arguments extracted from the query.
+    pub arguments: Vec<String>,
+    /// Why the rule did or didn't match.
+    pub detail: String,
+}
+
+impl Step {
+    pub(crate) fn new(rule: Rule, matched: bool, detail: impl Into<String>) -> Self {
+        Self {
+            rule,
+            matched,
+            keyword: None,
+            arguments: Vec::new(),
+            detail: detail.into(),
+        }
+    }
+
+    pub(crate) fn keyword(mut self, keyword: &str) -> Self {
+        self.keyword = Some(keyword.to_string());
+        self
+    }
+
+    pub(crate) fn arguments(mut self, arguments: &[String]) -> Self {
+        self.arguments = arguments.to_vec();
+        self
+    }
+}
+
+/// Collects steps while resolving, if enabled.
+#[derive(Debug, Default)]
+pub(crate) struct Trace {
+    enabled: bool,
+    steps: Vec<Step>,
+}
+
+impl Trace {
+    pub(crate) fn enabled() -> Self {
+        Self {
+            enabled: true,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Records a step, which is only built when tracing.
+    pub(crate) fn record(&mut self, step: impl FnOnce() -> Step) {
+        if self.enabled {
+            self.steps.push(step());
+        }
+    }
+
+    pub(crate) fn into_steps(self) -> Vec<Step> {
+        self.steps
+    }
+}
+
+/// How a query was resolved: every rule that was tried and the final url, or
+/// the error that stopped it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Explanation {
+    pub query: String,
+    pub steps: Vec<Step>,
+    pub url: Option<Url>,
+    pub error: Option<String>,
+}
+
+impl Explanation {
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(HEADER);
+        let _ = writeln!(html, "<h1><code>{}</code></h1>", escape(&self.query));
+        html.push_str("<ol>\n");
+        for step in &self.steps {
+            let _ = write!(
+                html,
+                "<li>{} {}",
+                escape(&step.rule.to_string()),
+                if step.matched {
+                    "matched"
+                } else {
+                    "didn't match"
+                }
+            );
+            if let Some(keyword) = &step.keyword {
+                let _ = write!(html, " <code>{}</code>", escape(keyword));
+            }
+            if !step.arguments.is_empty() {
+                let arguments: Vec<_> = step
+                    .arguments
+                    .iter()
+                    .map(|argument| format!("<code>{}</code>", escape(argument)))
+                    .collect();
+                let _ = write!(html, " with arguments {}", arguments.join(", "));
+            }
+            let _ = writeln!(html, ": {}</li>", escape(&step.detail));
+        }
+        html.push_str("</ol>\n");
+        match (&self.url, &self.error) {
+            (Some(url), _) => {
+                let url = escape(url.as_str());
+                let _ = writeln!(html, "<p>Goes to <a href=\"{}\">{}</a></p>", url, url);
+            }
+            (None, Some(error)) => {
+                let _ = writeln!(html, "<p>Failed: {}</p>", escape(error));
+            }
+            (None, None) => {}
+        }
+        html.push_str("</html>\n");
+        html
+    }
+}
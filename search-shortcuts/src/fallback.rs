@@ -0,0 +1,229 @@
+use crate::errors::Result;
+use crate::query::ParsedQuery;
+use crate::template::Template;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafeSearch {
+    Off,
+    Moderate,
+    Strict,
+}
+
+/// The search engine used for queries that don't match any shortcut.
+///
+/// In a registry config this is the `[fallback]` table, eg
+///
+/// ```toml
+/// [fallback]
+/// engine = "duckduckgo"
+/// region = "au-en"
+/// safe_search = "strict"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "engine", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Fallback {
+    #[serde(rename = "duckduckgo")]
+    DuckDuckGo {
+        /// The `kl` region code, eg `au-en`.
+        region: Option<String>,
+        safe_search: Option<SafeSearch>,
+        /// The `kae` theme code, eg `d` for dark.
+        theme: Option<String>,
+    },
+    Google {
+        safe_search: Option<SafeSearch>,
+    },
+    Bing {
+        safe_search: Option<SafeSearch>,
+    },
+    Brave {
+        safe_search: Option<SafeSearch>,
+    },
+    Kagi {},
+    Startpage {},
+    /// A SearXNG instance, where `url` is its search endpoint, eg
+    /// `https://searx.example.com/search`.
+    Searxng {
+        url: Url,
+        safe_search: Option<SafeSearch>,
+    },
+    /// Any other engine, where `{rest}` in the template is the whole query.
+    Custom {
+        template: Template,
+    },
+}
+
+impl Default for Fallback {
+    fn default() -> Self {
+        Fallback::DuckDuckGo {
+            region: None,
+            safe_search: None,
+            theme: None,
+        }
+    }
+}
+
+impl Fallback {
+    /// Whether the engine understands DuckDuckGo style `!bang`s itself.
+    pub fn supports_bangs(&self) -> bool {
+        matches!(
+            self,
+            Fallback::DuckDuckGo { .. } | Fallback::Brave { .. } | Fallback::Kagi {}
+        )
+    }
+
+    pub fn url(&self, query: &str) -> Result<Url> {
+        Ok(match self {
+            Fallback::DuckDuckGo {
+                region,
+                safe_search,
+                theme,
+            } => {
+                // k1=-1 turns off ads
+                let mut url = Url::parse("https://duckduckgo.com/?k1=-1")?;
+                {
+                    let mut pairs = url.query_pairs_mut();
+                    if let Some(region) = region {
+                        pairs.append_pair("kl", region);
+                    }
+                    if let Some(safe_search) = safe_search {
+                        let kp = match safe_search {
+                            SafeSearch::Off => "-2",
+                            SafeSearch::Moderate => "-1",
+                            SafeSearch::Strict => "1",
+                        };
+                        pairs.append_pair("kp", kp);
+                    }
+                    if let Some(theme) = theme {
+                        pairs.append_pair("kae", theme);
+                    }
+                    pairs.append_pair("q", query);
+                }
+                url
+            }
+            Fallback::Google { safe_search } => {
+                let mut url =
+                    Url::parse_with_params("https://www.google.com/search", &[("q", query)])?;
+                if let Some(safe_search) = safe_search {
+                    let safe = match safe_search {
+                        SafeSearch::Off => "off",
+                        SafeSearch::Moderate | SafeSearch::Strict => "active",
+                    };
+                    url.query_pairs_mut().append_pair("safe", safe);
+                }
+                url
+            }
+            Fallback::Bing { safe_search } => {
+                let mut url =
+                    Url::parse_with_params("https://www.bing.com/search", &[("q", query)])?;
+                if let Some(safe_search) = safe_search {
+                    url.query_pairs_mut()
+                        .append_pair("adlt", safe_search_name(*safe_search));
+                }
+                url
+            }
+            Fallback::Brave { safe_search } => {
+                let mut url =
+                    Url::parse_with_params("https://search.brave.com/search", &[("q", query)])?;
+                if let Some(safe_search) = safe_search {
+                    url.query_pairs_mut()
+                        .append_pair("safesearch", safe_search_name(*safe_search));
+                }
+                url
+            }
+            Fallback::Kagi {} => {
+                Url::parse_with_params("https://kagi.com/search", &[("q", query)])?
+            }
+            Fallback::Startpage {} => {
+                Url::parse_with_params("https://www.startpage.com/sp/search", &[("query", query)])?
+            }
+            Fallback::Searxng { url, safe_search } => {
+                let mut url = url.clone();
+                {
+                    let mut pairs = url.query_pairs_mut();
+                    pairs.append_pair("q", query);
+                    if let Some(safe_search) = safe_search {
+                        let level = match safe_search {
+                            SafeSearch::Off => "0",
+                            SafeSearch::Moderate => "1",
+                            SafeSearch::Strict => "2",
+                        };
+                        pairs.append_pair("safesearch", level);
+                    }
+                }
+                url
+            }
+            Fallback::Custom { template } => template.render(&ParsedQuery::new("", query))?,
+        })
+    }
+}
+
+fn safe_search_name(safe_search: SafeSearch) -> &'static str {
+    match safe_search {
+        SafeSearch::Off => "off",
+        SafeSearch::Moderate => "moderate",
+        SafeSearch::Strict => "strict",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(
+        r#"engine = "duckduckgo""#,
+        "https://duckduckgo.com/?k1=-1&q=lol+donkey"
+    )]
+    #[test_case(
+        r#"engine = "duckduckgo"
+        region = "au-en"
+        safe_search = "strict"
+        theme = "d""#,
+        "https://duckduckgo.com/?k1=-1&kl=au-en&kp=1&kae=d&q=lol+donkey"
+    )]
+    #[test_case(
+        r#"engine = "google"
+        safe_search = "strict""#,
+        "https://www.google.com/search?q=lol+donkey&safe=active"
+    )]
+    #[test_case(r#"engine = "bing""#, "https://www.bing.com/search?q=lol+donkey")]
+    #[test_case(
+        r#"engine = "brave"
+        safe_search = "off""#,
+        "https://search.brave.com/search?q=lol+donkey&safesearch=off"
+    )]
+    #[test_case(r#"engine = "kagi""#, "https://kagi.com/search?q=lol+donkey")]
+    #[test_case(
+        r#"engine = "startpage""#,
+        "https://www.startpage.com/sp/search?query=lol+donkey"
+    )]
+    #[test_case(
+        r#"engine = "searxng"
+        url = "https://searx.example.com/search"
+        safe_search = "moderate""#,
+        "https://searx.example.com/search?q=lol+donkey&safesearch=1"
+    )]
+    #[test_case(
+        r#"engine = "custom"
+        template = "https://search.example.com/?query={rest}""#,
+        "https://search.example.com/?query=lol+donkey"
+    )]
+    fn fallback_url(config: &str, expected: &str) -> Result<()> {
+        let fallback: Fallback = toml::from_str(config)?;
+        assert_eq!(expected, fallback.url("lol donkey")?.as_str());
+        Ok(())
+    }
+
+    #[test_case(r#"engine = "altavista""# ; "unknown engine")]
+    #[test_case(r#"engine = "searxng""# ; "searxng without url")]
+    #[test_case(r#"engine = "kagi"
+        region = "au-en""# ; "unknown option")]
+    fn invalid_fallback(config: &str) {
+        assert!(toml::from_str::<Fallback>(config).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! Matching mistyped keywords, eg `dcos` for `docs` or `ghh` for `gh`.
+//!
+//! Typos are scored with an edit distance where the slips that are common on
+//! a phone keyboard cost half as much as any other edit.
+
+/// The cost of a likely slip: hitting a neighbouring key, swapping two
+/// letters or hitting a key twice.
+pub(crate) const SLIP: u32 = 1;
+/// The cost of any other edit.
+pub(crate) const EDIT: u32 = 2;
+
+const ROWS: [(&str, f32); 4] = [
+    ("1234567890", 0.0),
+    ("qwertyuiop", 0.5),
+    ("asdfghjkl", 0.75),
+    ("zxcvbnm", 1.25),
+];
+
+fn position(c: char) -> Option<(f32, f32)> {
+    ROWS.iter().enumerate().find_map(|(row, (keys, offset))| {
+        keys.find(c.to_ascii_lowercase())
+            .map(|column| (column as f32 + offset, row as f32))
+    })
+}
+
+/// Whether two keys are next to each other on a QWERTY keyboard.
+fn adjacent(a: char, b: char) -> bool {
+    match (position(a), position(b)) {
+        (Some((ax, ay)), Some((bx, by))) => {
+            a != b && (ax - bx).abs() <= 1.0 && (ay - by).abs() <= 1.0
+        }
+        _ => false,
+    }
+}
+
+/// The cost of turning `keyword` into what was `typed` by inserting,
+/// deleting, substituting or swapping letters, with slips costing [`SLIP`]
+/// and everything else [`EDIT`].
+pub(crate) fn distance(typed: &str, keyword: &str) -> u32 {
+    let typed: Vec<char> = typed.chars().collect();
+    let keyword: Vec<char> = keyword.chars().collect();
+    // costs[i][j] is the cost of turning keyword[..j] into typed[..i]
+    let mut costs = vec![vec![0; keyword.len() + 1]; typed.len() + 1];
+    for (i, row) in costs.iter_mut().enumerate() {
+        row[0] = i as u32 * EDIT;
+    }
+    for (j, cost) in costs[0].iter_mut().enumerate() {
+        *cost = j as u32 * EDIT;
+    }
+    for i in 1..=typed.len() {
+        for j in 1..=keyword.len() {
+            let (t, k) = (typed[i - 1], keyword[j - 1]);
+            let substitution = match t == k {
+                true => 0,
+                false if adjacent(t, k) => SLIP,
+                false => EDIT,
+            };
+            // an extra letter is a slip if it's a repeat of the one before
+            let insertion = match i > 1 && typed[i - 2] == t {
+                true => SLIP,
+                false => EDIT,
+            };
+            let mut cost = (costs[i - 1][j - 1] + substitution)
+                .min(costs[i - 1][j] + insertion)
+                .min(costs[i][j - 1] + EDIT);
+            if i > 1 && j > 1 && t == keyword[j - 2] && typed[i - 2] == k {
+                cost = cost.min(costs[i - 2][j - 2] + SLIP);
+            }
+            costs[i][j] = cost;
+        }
+    }
+    costs[typed.len()][keyword.len()]
+}
+
+/// Whether `typed` is a slip away from `keyword`. Short keywords only count
+/// doubled letters as almost any two letter word is a slip away from one of
+/// them.
+pub(crate) fn is_slip(typed: &str, keyword: &str) -> bool {
+    let distance = distance(typed, keyword);
+    match keyword.chars().count() {
+        0 | 1 => false,
+        2 => distance == SLIP && typed.chars().count() == 3,
+        _ => distance == SLIP,
+    }
+}
+
+/// Whether `typed` can be corrected to `keyword` without asking: a doubled or
+/// swapped letter in a keyword of at least four letters. Hitting a
+/// neighbouring key too easily makes another word (`cord` for `core`) and
+/// most slips on shorter keywords do too (`app` for `ap`), so those are only
+/// suggested.
+pub(crate) fn is_autocorrectable(typed: &str, keyword: &str) -> bool {
+    let typed: Vec<char> = typed.chars().collect();
+    let keyword: Vec<char> = keyword.chars().collect();
+    if keyword.len() < 4 {
+        return false;
+    }
+    let start = typed
+        .iter()
+        .zip(&keyword)
+        .take_while(|(t, k)| t == k)
+        .count();
+    if typed.len() == keyword.len() + 1 {
+        // the extra letter repeats the one before, eg `twiir`
+        start > 0 && typed[start] == typed[start - 1] && typed[start + 1..] == keyword[start..]
+    } else if typed.len() == keyword.len() && start + 1 < typed.len() {
+        // the next two letters are swapped, eg `dcos`
+        typed[start] == keyword[start + 1]
+            && typed[start + 1] == keyword[start]
+            && typed[start + 2..] == keyword[start + 2..]
+    } else {
+        false
+    }
+}
+
+/// Whether `typed` is close enough to `keyword` to ask if it was meant.
+pub(crate) fn is_near_miss(typed: &str, keyword: &str) -> bool {
+    keyword.chars().count() >= 5 && distance(typed, keyword) <= EDIT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("docs", "docs", 0)]
+    #[test_case("dcos", "docs", SLIP ; "swapped")]
+    #[test_case("ghh", "gh", SLIP ; "doubled")]
+    #[test_case("fh", "gh", SLIP ; "neighbouring key")]
+    #[test_case("oh", "gh", EDIT ; "distant key")]
+    #[test_case("crate", "crates", EDIT ; "missing letter")]
+    #[test_case("ghx", "gh", EDIT ; "extra letter")]
+    #[test_case("", "gh", 2 * EDIT)]
+    fn distances(typed: &str, keyword: &str, expected: u32) {
+        assert_eq!(expected, distance(typed, keyword));
+    }
+
+    #[test_case("ghh", "gh", true)]
+    #[test_case("by", "bt", false ; "short keywords only allow doubled letters")]
+    #[test_case("dcos", "docs", true)]
+    #[test_case("twiir", "twir", true)]
+    #[test_case("doc", "docs", false)]
+    fn slips(typed: &str, keyword: &str, expected: bool) {
+        assert_eq!(expected, is_slip(typed, keyword));
+    }
+
+    #[test_case("dcos", "docs", true ; "swapped")]
+    #[test_case("twiir", "twir", true ; "doubled")]
+    #[test_case("docss", "docs", true ; "doubled last letter")]
+    #[test_case("ddocs", "docs", true ; "doubled first letter")]
+    #[test_case("twit", "twir", false ; "neighbouring key")]
+    #[test_case("cord", "core", false ; "neighbouring key makes a word")]
+    #[test_case("ghh", "gh", false ; "short keyword")]
+    #[test_case("app", "ap", false ; "doubled letter in a short keyword")]
+    #[test_case("abd", "abc", false ; "three letter keyword")]
+    #[test_case("docs", "docs", false ; "no typo")]
+    #[test_case("dsco", "docs", false ; "two letters apart")]
+    fn autocorrectable(typed: &str, keyword: &str, expected: bool) {
+        assert_eq!(expected, is_autocorrectable(typed, keyword));
+    }
+}
@@ -0,0 +1,220 @@
+//! The shortcuts that ship with this crate, which can also be registered
+//! under different keywords by naming them in a registry config file.
+
+use crate::errors::{Error, Result};
+use crate::query::ParsedQuery;
+use crate::shortcut::{Resolution, Shortcut};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+fn handle_docs(query: &ParsedQuery) -> Result<Url> {
+    let target = query.arg(0).unwrap_or_default();
+    let crate_ = target.split(['/', '@']).next().unwrap_or_default();
+    if !crate_
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(Error::invalid_argument(
+            target,
+            "crate names only contain letters, numbers, `-` and `_`",
+        ));
+    }
+    Ok(if target == "std" {
+        Url::parse("https://doc.rust-lang.org/stable/std/")?
+    } else {
+        let url = Url::parse("https://docs.rs/")?;
+        match target.split_once(['/', '@']) {
+            // crate + version
+            Some((crate_, version)) if !version.is_empty() => {
+                url.join(&format!("{}/{}", crate_, version))?
+            }
+            // crate
+            _ if target.ends_with('/') => url.join(target)?,
+            _ => url.join(&format!("{}/", target))?,
+        }
+    })
+}
+
+fn handle_github(query: &ParsedQuery) -> Result<Url> {
+    let url = Url::parse("https://github.com/")?;
+    let target = query.arg(0).unwrap_or_default();
+    Ok(
+        if let Some(user) = target
+            .strip_prefix('@')
+            .or_else(|| target.strip_prefix("u/"))
+        {
+            url.join(user)?
+        } else if target.contains('/') {
+            let reference = query.arg(1).unwrap_or_default();
+            // github treats issues and prs the same, but this distinction
+            // prevents an unneccesary redirect
+            if let Some(issue) = reference.strip_prefix('#') {
+                url.join(&format!("{}/issues/{}", target, number(reference, issue)?))?
+            } else if let Some(pr) = reference.strip_prefix('!') {
+                url.join(&format!("{}/pull/{}", target, number(reference, pr)?))?
+            } else {
+                url.join(target)?
+            }
+        } else {
+            let mut url = url.join("search")?;
+            url.query_pairs_mut().append_pair("q", &query.raw_rest);
+            url
+        },
+    )
+}
+
+/// Checks an issue or pull request number, which is `reference` without its
+/// `#` or `!`.
+fn number<'a>(reference: &str, number: &'a str) -> Result<&'a str> {
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Error::invalid_argument(
+            reference,
+            "issues and pull requests are numbers, eg `#1` or `!1`",
+        ));
+    }
+    Ok(number)
+}
+
+fn handle_docker_hub(query: &ParsedQuery) -> Result<Url> {
+    let url = Url::parse("https://hub.docker.com/")?;
+    let target = query.arg(0).unwrap_or_default();
+    Ok(if let Some(image) = target.strip_prefix("r/") {
+        if !image.contains('/') {
+            url.join("_/")?.join(image)?
+        } else {
+            url.join("r/")?.join(image)?
+        }
+    } else if let Some(image) = target.strip_prefix('/') {
+        url.join("_/")?.join(image)?
+    } else if let Some(image) = target.strip_prefix("_/") {
+        url.join("_/")?.join(image)?
+    } else if target.contains('/') {
+        url.join("r/")?.join(target)?
+    } else {
+        let mut url = url.join("search")?;
+        url.query_pairs_mut().append_pair("q", &query.raw_rest);
+        url
+    })
+}
+
+fn handle_twitch(query: &ParsedQuery) -> Result<Url> {
+    let target = query.arg(0).unwrap_or_default();
+    Ok(match target.strip_prefix('@') {
+        Some(user) => Url::parse("https://www.twitch.tv/")?.join(user)?,
+        None => {
+            Url::parse_with_params("https://www.twitch.tv/search", &[("term", &query.raw_rest)])?
+        }
+    })
+}
+
+macro_rules! builtin {
+    ($name:ident, $keyword:literal, $description:literal, $category:literal, [$($example:literal),*], $handle:ident) => {
+        #[doc = $description]
+        pub struct $name;
+
+        impl Shortcut for $name {
+            fn keywords(&self) -> Vec<&str> {
+                vec![$keyword]
+            }
+
+            fn description(&self) -> &str {
+                $description
+            }
+
+            fn category(&self) -> &str {
+                $category
+            }
+
+            fn examples(&self) -> Vec<&str> {
+                vec![$($example),*]
+            }
+
+            fn resolve(&self, query: &ParsedQuery) -> Result<Resolution> {
+                Ok($handle(query)?.into())
+            }
+        }
+    };
+}
+
+/// Rust documentation on docs.rs, or the standard library docs for `std`.
+pub struct Docs;
+
+impl Shortcut for Docs {
+    fn keywords(&self) -> Vec<&str> {
+        vec!["docs"]
+    }
+
+    fn description(&self) -> &str {
+        "Rust documentation on docs.rs, or the standard library docs for `std`."
+    }
+
+    fn category(&self) -> &str {
+        "Development"
+    }
+
+    fn examples(&self) -> Vec<&str> {
+        vec!["docs serde", "docs std", "docs tokio/1.0.0"]
+    }
+
+    fn separators(&self) -> Vec<&str> {
+        vec![" ", "/"]
+    }
+
+    fn resolve(&self, query: &ParsedQuery) -> Result<Resolution> {
+        Ok(handle_docs(query)?.into())
+    }
+}
+
+builtin!(
+    Github,
+    "gh",
+    "GitHub users (`@user`), repos, issues (`#1`), pull requests (`!1`) or search.",
+    "Development",
+    [
+        "gh @fullylegit",
+        "gh rust-lang/rust",
+        "gh rust-lang/rust #1",
+        "gh rust-lang/rust !168",
+        "gh borrow checker"
+    ],
+    handle_github
+);
+builtin!(
+    DockerHub,
+    "dh",
+    "Docker Hub official images (`/nginx`), repos or search.",
+    "Development",
+    ["dh /nginx", "dh grafana/grafana", "dh postgres"],
+    handle_docker_hub
+);
+builtin!(
+    Twitch,
+    "t",
+    "Twitch channels (`@user`) or search.",
+    "Entertainment",
+    ["t @user", "t speedrunning"],
+    handle_twitch
+);
+
+/// The built-in handlers a shortcut in the registry can dispatch to, named
+/// in the config file in snake_case (eg `handler = "docker_hub"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Handler {
+    Docs,
+    Github,
+    DockerHub,
+    Twitch,
+}
+
+impl Handler {
+    pub(crate) fn shortcut(self) -> &'static dyn Shortcut {
+        match self {
+            Handler::Docs => &Docs,
+            Handler::Github => &Github,
+            Handler::DockerHub => &DockerHub,
+            Handler::Twitch => &Twitch,
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! The help page listing every shortcut, which both front ends serve for the
+//! `help` query and at `/help`.
+
+use crate::errors::Error;
+use crate::html::{escape, HEADER};
+use crate::resolver::Resolver;
+use crate::shortcut::Arguments;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use url::{form_urlencoded, Url};
+
+/// The query that shows the help page, unless a shortcut uses the keyword.
+pub const HELP_KEYWORD: &str = "help";
+
+/// What the help page shows for one shortcut.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpEntry<'a> {
+    pub keyword: &'a str,
+    pub aliases: Vec<&'a str>,
+    pub description: &'a str,
+    pub category: &'a str,
+    /// Example queries and where they go, or `None` if an example doesn't
+    /// resolve.
+    pub examples: Vec<(&'a str, Option<Url>)>,
+}
+
+/// Whether the query asks for the help page.
+pub fn is_help_query(resolver: &Resolver, query: &str) -> bool {
+    query.trim().eq_ignore_ascii_case(HELP_KEYWORD)
+        && !resolver
+            .shortcuts()
+            .any(|shortcut| shortcut.keywords().contains(&HELP_KEYWORD))
+}
+
+/// Lists the resolver's shortcuts in the order they're tried.
+pub fn entries(resolver: &Resolver) -> Vec<HelpEntry<'_>> {
+    resolver
+        .shortcuts()
+        .map(|shortcut| {
+            let keywords = shortcut.keywords();
+            let mut examples = shortcut.examples();
+            if examples.is_empty() && shortcut.arguments() != Arguments::Required {
+                examples.push(keywords[0]);
+            }
+            HelpEntry {
+                keyword: keywords[0],
+                aliases: keywords[1..].to_vec(),
+                description: shortcut.description(),
+                category: shortcut.category(),
+                examples: examples
+                    .into_iter()
+                    .map(|example| (example, resolver.resolve(example).ok().map(|r| r.url)))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Lists the resolver's patterns in the order they're tried, using each
+/// pattern's name as the keyword.
+pub fn pattern_entries(resolver: &Resolver) -> Vec<HelpEntry<'_>> {
+    let mut patterns: Vec<_> = resolver.patterns().collect();
+    patterns.sort_by_key(|pattern| pattern.priority());
+    patterns
+        .into_iter()
+        .map(|pattern| HelpEntry {
+            keyword: pattern.name(),
+            aliases: Vec::new(),
+            description: pattern.description(),
+            category: pattern.category(),
+            examples: pattern
+                .examples()
+                .into_iter()
+                .map(|example| (example, resolver.resolve(example).ok().map(|r| r.url)))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Renders the help page, with the shortcuts grouped by category.
+pub fn render(resolver: &Resolver) -> String {
+    let mut categories: BTreeMap<&str, Vec<HelpEntry>> = BTreeMap::new();
+    for entry in entries(resolver) {
+        categories.entry(entry.category).or_default().push(entry);
+    }
+    // uncategorised shortcuts go last
+    let other = categories.remove("");
+
+    let mut html = String::from(HEADER);
+    html.push_str(concat!(
+        "<h1>Shortcuts</h1>\n",
+        "<p>Type a keyword, optionally followed by a query. ",
+        "Anything that doesn't match a keyword is searched for instead.</p>\n",
+        "<p>Pasted DOIs, arXiv ids, PubMed ids (<code>PMID 31452104</code>) ",
+        "and ISBNs go straight to the paper or book.</p>\n",
+    ));
+    for (category, entries) in categories
+        .iter()
+        .map(|(category, entries)| (*category, entries))
+        .chain(other.as_ref().map(|entries| ("Other", entries)))
+    {
+        let _ = writeln!(html, "<h2>{}</h2>", escape(category));
+        render_table(&mut html, "Keyword", entries);
+    }
+    let patterns = pattern_entries(resolver);
+    if !patterns.is_empty() {
+        html.push_str(concat!(
+            "<h2>Patterns</h2>\n",
+            "<p>These match what's pasted or typed without a keyword.</p>\n",
+        ));
+        render_table(&mut html, "Pattern", &patterns);
+    }
+    html.push_str("</html>\n");
+    html
+}
+
+/// Renders the page shown when a query can't be resolved, with the usage of
+/// the shortcut the query was meant for if it can be worked out.
+pub fn render_error(resolver: &Resolver, query: &str, err: &Error) -> String {
+    let keyword = match err {
+        Error::DidYouMean(queries) => queries
+            .first()
+            .and_then(|query| query.split_whitespace().next())
+            .unwrap_or_default(),
+        _ => query
+            .split_whitespace()
+            .find_map(|token| token.strip_prefix('!').filter(|k| !k.is_empty()))
+            .or_else(|| query.split_whitespace().next())
+            .unwrap_or_default(),
+    };
+    let usage: Vec<_> = entries(resolver)
+        .into_iter()
+        .filter(|entry| {
+            std::iter::once(&entry.keyword)
+                .chain(&entry.aliases)
+                .any(|k| k.eq_ignore_ascii_case(keyword))
+        })
+        .collect();
+
+    let mut html = String::from(HEADER);
+    let _ = writeln!(
+        html,
+        "<h1>Couldn't resolve <code>{}</code></h1>",
+        escape(query)
+    );
+    let _ = writeln!(html, "<p>{}</p>", escape(&err.to_string()));
+    if let Error::DidYouMean(queries) = err {
+        html.push_str("<ul>\n");
+        for suggestion in queries {
+            let href: String = form_urlencoded::byte_serialize(suggestion.as_bytes()).collect();
+            let _ = writeln!(
+                html,
+                "<li><a href=\"/?q={}\"><code>{}</code></a></li>",
+                escape(&href),
+                escape(suggestion)
+            );
+        }
+        if let Ok(url) = resolver.fallback().url(query) {
+            let _ = writeln!(
+                html,
+                "<li><a href=\"{}\">Search the web for <code>{}</code></a></li>",
+                escape(url.as_str()),
+                escape(query)
+            );
+        }
+        html.push_str("</ul>\n");
+    }
+    if !usage.is_empty() {
+        html.push_str("<h2>Usage</h2>\n");
+        render_table(&mut html, "Keyword", &usage);
+    }
+    html.push_str("<p><a href=\"/help\">All shortcuts</a></p>\n");
+    html.push_str("</html>\n");
+    html
+}
+
+/// Renders the page asking before opening a url from the query, for
+/// requests that [`Safeguards::needs_interstitial`] doesn't trust.
+///
+/// [`Safeguards::needs_interstitial`]: crate::safeguards::Safeguards::needs_interstitial
+pub fn render_interstitial(resolver: &Resolver, query: &str, url: &Url) -> String {
+    let mut html = String::from(HEADER);
+    let _ = writeln!(
+        html,
+        "<h1>You're leaving for <code>{}</code></h1>",
+        escape(url.host_str().unwrap_or_default())
+    );
+    html.push_str(concat!(
+        "<p>This link goes to a site that isn't part of this one. ",
+        "Only continue if you trust where it came from.</p>\n",
+        "<ul>\n",
+    ));
+    let _ = writeln!(
+        html,
+        "<li><a href=\"{}\" rel=\"noreferrer\">Continue to <code>{}</code></a></li>",
+        escape(url.as_str()),
+        escape(url.as_str())
+    );
+    if let Ok(search) = resolver.fallback().url(query) {
+        let _ = writeln!(
+            html,
+            "<li><a href=\"{}\">Search the web for <code>{}</code></a></li>",
+            escape(search.as_str()),
+            escape(query)
+        );
+    }
+    html.push_str("</ul>\n");
+    html.push_str("</html>\n");
+    html
+}
+
+fn render_table(html: &mut String, heading: &str, entries: &[HelpEntry]) {
+    html.push_str("<table>\n");
+    let _ = writeln!(
+        html,
+        "<tr><th>{}</th><th>Aliases</th><th>Description</th><th>Examples</th></tr>",
+        heading
+    );
+    for entry in entries {
+        render_entry(html, entry);
+    }
+    html.push_str("</table>\n");
+}
+
+fn render_entry(html: &mut String, entry: &HelpEntry) {
+    let aliases: Vec<_> = entry
+        .aliases
+        .iter()
+        .map(|alias| format!("<code>{}</code>", escape(alias)))
+        .collect();
+    let _ = write!(
+        html,
+        "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>",
+        escape(entry.keyword),
+        aliases.join(", "),
+        code_spans(&escape(entry.description))
+    );
+    if !entry.examples.is_empty() {
+        html.push_str("<ul>");
+        for (example, url) in &entry.examples {
+            let _ = write!(html, "<li><code>{}</code>", escape(example));
+            if let Some(url) = url {
+                let url = escape(url.as_str());
+                let _ = write!(html, " &rarr; <a href=\"{}\">{}</a>", url, url);
+            }
+            html.push_str("</li>");
+        }
+        html.push_str("</ul>");
+    }
+    html.push_str("</td></tr>\n");
+}
+
+/// Turns the `code` spans used in descriptions into `<code>` elements.
+fn code_spans(text: &str) -> String {
+    let mut html = String::new();
+    for (i, part) in text.split('`').enumerate() {
+        if i % 2 == 1 {
+            let _ = write!(html, "<code>{}</code>", part);
+        } else {
+            html.push_str(part);
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::Registry;
+    use test_case::test_case;
+
+    #[test_case("help", true)]
+    #[test_case(" Help ", true ; "mobile autocomplete")]
+    #[test_case("help me", false)]
+    fn help_query(query: &str, expected: bool) {
+        assert_eq!(expected, is_help_query(&Resolver::default(), query));
+    }
+
+    #[test]
+    fn help_keyword_can_be_overridden() -> crate::errors::Result<()> {
+        let resolver = Resolver::builder()
+            .registry(Registry::from_toml(
+                r#"
+                [[redirect]]
+                keyword = "help"
+                url = "https://help.example.com/"
+                "#,
+            )?)
+            .build();
+        assert!(!is_help_query(&resolver, "help"));
+        Ok(())
+    }
+
+    #[test]
+    fn default_entries() {
+        let resolver = Resolver::default();
+        let patterns = pattern_entries(&resolver);
+        let entries = entries(&resolver);
+        let slashdot = entries.iter().find(|e| e.keyword == "sd").unwrap();
+        assert_eq!(vec!["/."], slashdot.aliases);
+        assert_eq!("News", slashdot.category);
+        assert_eq!(
+            vec![("sd", Some(Url::parse("https://slashdot.org").unwrap()))],
+            slashdot.examples
+        );
+        // every example in the default config should resolve
+        for entry in entries.iter().chain(&patterns) {
+            for (example, url) in &entry.examples {
+                assert!(url.is_some(), "{:?} doesn't resolve", example);
+            }
+        }
+    }
+
+    #[test]
+    fn render_page() {
+        let html = render(&Resolver::default());
+        assert!(html.contains("<h2>Development</h2>"), "{}", html);
+        assert!(
+            html.contains(
+                "<li><code>gh rust-lang/rust #1</code> &rarr; <a href=\"https://github.com/rust-lang/rust/issues/1\">"
+            ),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("GitHub users (<code>@user</code>)"),
+            "{}",
+            html
+        );
+        assert!(!html.contains("<h2>Other</h2>"), "{}", html);
+        assert!(
+            html.contains("<tr><th>Pattern</th>") && html.contains("<code>rust-error</code>"),
+            "{}",
+            html
+        );
+    }
+
+    #[test]
+    fn render_error_page() {
+        let resolver = Resolver::default();
+        let err = resolver.resolve("gh rust-lang/rust #one").unwrap_err();
+        assert_eq!(400, err.status());
+        let html = render_error(&resolver, "gh rust-lang/rust #one", &err);
+        assert!(html.contains("<h2>Usage</h2>"), "{}", html);
+        assert!(
+            html.contains("<code>gh rust-lang/rust #1</code>"),
+            "{}",
+            html
+        );
+    }
+
+    #[test]
+    fn render_did_you_mean_page() {
+        let resolver = Resolver::default();
+        let err = resolver.resolve("twit 520").unwrap_err();
+        let html = render_error(&resolver, "twit 520", &err);
+        assert!(
+            html.contains("<a href=\"/?q=twir+520\"><code>twir 520</code></a>"),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("<a href=\"https://duckduckgo.com/?k1=-1&amp;q=twit+520\">"),
+            "{}",
+            html
+        );
+    }
+
+    #[test]
+    fn render_interstitial_page() {
+        let resolver = Resolver::default();
+        let url = Url::parse("https://evil.example/login?a=1&b=2").unwrap();
+        let html = render_interstitial(&resolver, "evil.example/login?a=1&b=2", &url);
+        assert!(
+            html.contains("<h1>You're leaving for <code>evil.example</code></h1>"),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("<a href=\"https://evil.example/login?a=1&amp;b=2\" rel=\"noreferrer\">"),
+            "{}",
+            html
+        );
+    }
+}
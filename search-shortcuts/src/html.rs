@@ -0,0 +1,24 @@
+//! Helpers for the HTML pages the crate renders.
+
+/// The start of every page, up to the body.
+pub(crate) const HEADER: &str = concat!(
+    "<!doctype html>\n",
+    "<html>\n",
+    "<meta charset=\"UTF-8\">\n",
+    "<title>shortcuts</title>\n",
+);
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,344 @@
+//! Recognising pasted identifiers for papers and books: DOIs, arXiv ids,
+//! PubMed ids and ISBNs.
+//!
+//! Identifiers that are only numbers need something to tell them apart from
+//! any other number: PubMed ids need a `PMID` prefix, ISBN-10s a prefix,
+//! hyphens or an `X` check digit, and every ISBN a valid check digit. With an
+//! `ISBN` prefix a wrong check digit is an error rather than a search.
+
+use crate::errors::{Error, Result};
+use crate::query::ParsedQuery;
+use crate::template::Template;
+
+use regex::Regex;
+use std::fmt;
+use std::sync::OnceLock;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+/// Where ISBNs go unless the config sets a `book_store`. The ISBN-13 is the
+/// query, eg `{rest}`.
+pub const DEFAULT_BOOK_STORE: &str =
+    "https://www.booktopia.com.au/search.ep?keywords={rest|query}&productType=917504";
+
+/// The arXiv archives that used old style ids, eg `hep-th/9901001`.
+const ARXIV_ARCHIVES: &[&str] = &[
+    "acc-phys", "adap-org", "alg-geom", "ao-sci", "astro-ph", "atom-ph", "bayes-an", "chao-dyn",
+    "chem-ph", "cmp-lg", "comp-gas", "cond-mat", "cs", "dg-ga", "funct-an", "gr-qc", "hep-ex",
+    "hep-lat", "hep-ph", "hep-th", "math", "math-ph", "mtrl-th", "nlin", "nucl-ex", "nucl-th",
+    "patt-sol", "physics", "plasm-ph", "q-alg", "q-bio", "quant-ph", "solv-int", "supr-con",
+];
+
+/// The current year, from the system clock.
+#[cfg(not(target_arch = "wasm32"))]
+fn current_year() -> u32 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400);
+    // the civil calendar from days since 1970, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let year = year_of_era + era * 400 + u64::from(month >= 10);
+    u32::try_from(year).unwrap_or(u32::MAX)
+}
+
+/// The current year, from JavaScript as std has no clock on wasm.
+#[cfg(target_arch = "wasm32")]
+fn current_year() -> u32 {
+    js_sys::Date::new_0().get_utc_full_year()
+}
+
+/// Whether the year and month of a new style arXiv id have happened, and the
+/// number has as many digits as the ids of that month: new style ids started
+/// in April 2007 and went from 4 to 5 digits in 2015.
+fn is_arxiv_date(year: u32, month: u32, digits: usize) -> bool {
+    let year = 2000 + year;
+    (1..=12).contains(&month)
+        && (year, month) >= (2007, 4)
+        && year <= current_year()
+        && digits == if year >= 2015 { 5 } else { 4 }
+}
+
+fn regex(cell: &'static OnceLock<Regex>, source: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(source).expect("identifier regexes are valid"))
+}
+
+/// An ISBN, stored as its 13 digits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Isbn(String);
+
+impl Isbn {
+    /// Parses an ISBN-10 or ISBN-13, ignoring hyphens and spaces.
+    pub fn parse(isbn: &str) -> Result<Self> {
+        let digits: String = isbn
+            .chars()
+            .filter(|c| *c != '-' && *c != ' ')
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        let (body, check) = match digits.len() {
+            10 | 13 => digits.split_at(digits.len() - 1),
+            _ => return Err(Error::invalid_argument(isbn, "an ISBN has 10 or 13 digits")),
+        };
+        if !body.chars().all(|c| c.is_ascii_digit()) {
+            return Err(Error::invalid_argument(isbn, "an ISBN is made of digits"));
+        }
+        let expected = match digits.len() {
+            10 => isbn10_check_digit(body),
+            _ if !body.starts_with("978") && !body.starts_with("979") => {
+                return Err(Error::invalid_argument(
+                    isbn,
+                    "an ISBN-13 starts with 978 or 979",
+                ))
+            }
+            _ => isbn13_check_digit(body),
+        };
+        if check != expected.to_string() {
+            return Err(Error::invalid_argument(
+                isbn,
+                &format!("the check digit should be {}", expected),
+            ));
+        }
+        match digits.len() {
+            10 => {
+                let body = format!("978{}", body);
+                let check = isbn13_check_digit(&body);
+                Ok(Self(format!("{}{}", body, check)))
+            }
+            _ => Ok(Self(digits)),
+        }
+    }
+
+    pub fn isbn13(&self) -> &str {
+        &self.0
+    }
+
+    /// The ISBN-10, which only ISBNs starting with 978 have.
+    pub fn isbn10(&self) -> Option<String> {
+        let body = self.0.strip_prefix("978")?;
+        let body = &body[..9];
+        Some(format!("{}{}", body, isbn10_check_digit(body)))
+    }
+}
+
+fn isbn10_check_digit(body: &str) -> char {
+    let sum: u32 = body
+        .chars()
+        .zip((2..=10).rev())
+        .map(|(c, weight)| c.to_digit(10).unwrap_or_default() * weight)
+        .sum();
+    match (11 - sum % 11) % 11 {
+        10 => 'X',
+        digit => char::from_digit(digit, 10).unwrap_or('0'),
+    }
+}
+
+fn isbn13_check_digit(body: &str) -> char {
+    let sum: u32 = body
+        .chars()
+        .zip([1, 3].into_iter().cycle())
+        .map(|(c, weight)| c.to_digit(10).unwrap_or_default() * weight)
+        .sum();
+    char::from_digit((10 - sum % 10) % 10, 10).unwrap_or('0')
+}
+
+/// An identifier found in a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identifier {
+    Doi(String),
+    Arxiv(String),
+    Pmid(String),
+    Isbn(Isbn),
+}
+
+impl Identifier {
+    /// Finds the identifier that makes up the whole query, if there is one.
+    pub fn detect(query: &str) -> Result<Option<Self>> {
+        static DOI: OnceLock<Regex> = OnceLock::new();
+        static ARXIV: OnceLock<Regex> = OnceLock::new();
+        static ARXIV_OLD: OnceLock<Regex> = OnceLock::new();
+        static PMID: OnceLock<Regex> = OnceLock::new();
+        static ISBN: OnceLock<Regex> = OnceLock::new();
+
+        let query = query.trim();
+        if let Some(captures) = regex(&DOI, r"^(?i:doi:\s*)?(10\.\d{4,9}/\S+)$").captures(query) {
+            return Ok(Some(Self::Doi(captures[1].to_string())));
+        }
+        let arxiv = regex(&ARXIV, r"^(?i:arxiv:\s*)?(\d{2})(\d{2})\.(\d{4,5})(v\d+)?$");
+        if let Some(captures) = arxiv.captures(query) {
+            let year = captures[1].parse().unwrap_or_default();
+            let month = captures[2].parse().unwrap_or_default();
+            if is_arxiv_date(year, month, captures[3].len()) {
+                return Ok(Some(Self::Arxiv(query_id(&captures))));
+            }
+        }
+        let arxiv_old = regex(
+            &ARXIV_OLD,
+            r"^(?i:arxiv:\s*)?([a-z-]+)(\.[A-Z]{2})?/(\d{2})(\d{2})\d{3}(v\d+)?$",
+        );
+        if let Some(captures) = arxiv_old.captures(query) {
+            let month = &captures[4];
+            if ARXIV_ARCHIVES.contains(&&captures[1]) && ("01".."13").contains(&month) {
+                return Ok(Some(Self::Arxiv(query_id(&captures))));
+            }
+        }
+        if let Some(captures) = regex(&PMID, r"^(?i)pmid:?\s*(\d{1,8})$").captures(query) {
+            return Ok(Some(Self::Pmid(captures[1].to_string())));
+        }
+        let isbn = regex(
+            &ISBN,
+            r"^(?i:isbn(?:-1[03])?:?\s*)?([\d][\d -]{8,15}[\dXx])$",
+        );
+        if let Some(captures) = isbn.captures(query) {
+            let number = &captures[1];
+            let prefixed = captures.get(0).map(|m| m.len()) != Some(number.len());
+            let digits = number.chars().filter(char::is_ascii_alphanumeric).count();
+            // a bare run of 10 digits is as likely to be a phone number
+            let formatted = number.contains('-') || number.ends_with(['X', 'x']);
+            return match Isbn::parse(number) {
+                Ok(isbn) if prefixed || digits == 13 || formatted => Ok(Some(Self::Isbn(isbn))),
+                Ok(_) => Ok(None),
+                Err(err) if prefixed => Err(err),
+                Err(_) => Ok(None),
+            };
+        }
+        Ok(None)
+    }
+
+    /// The name used for the identifier in explanations, like a keyword.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Doi(_) => "doi",
+            Self::Arxiv(_) => "arxiv",
+            Self::Pmid(_) => "pmid",
+            Self::Isbn(_) => "isbn",
+        }
+    }
+
+    /// Where the identifier goes, with ISBNs going to `book_store`.
+    pub fn url(&self, book_store: &Template) -> Result<Url> {
+        let mut url = match self {
+            Self::Doi(_) => Url::parse("https://doi.org/")?,
+            Self::Arxiv(_) => Url::parse("https://arxiv.org/abs/")?,
+            Self::Pmid(pmid) => {
+                return Ok(Url::parse(&format!(
+                    "https://pubmed.ncbi.nlm.nih.gov/{}/",
+                    pmid
+                ))?)
+            }
+            Self::Isbn(isbn) => return book_store.render(&ParsedQuery::new("isbn", isbn.isbn13())),
+        };
+        // DOIs and old style arXiv ids contain slashes that belong in the path
+        let path = format!("{}{}", url.path(), self);
+        url.set_path(&path);
+        Ok(url)
+    }
+}
+
+/// An arXiv id without any `arXiv:` prefix.
+fn query_id(captures: &regex::Captures) -> String {
+    let whole = &captures[0];
+    match whole.find(':') {
+        Some(colon) => whole[colon + 1..].trim_start().to_string(),
+        None => whole.to_string(),
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Doi(id) | Self::Arxiv(id) | Self::Pmid(id) => f.write_str(id),
+            Self::Isbn(isbn) => f.write_str(isbn.isbn13()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("0-306-40615-2", "9780306406157" ; "isbn 10")]
+    #[test_case("978-0-306-40615-7", "9780306406157" ; "isbn 13")]
+    #[test_case("0-8044-2957-x", "9780804429573" ; "check digit x")]
+    #[test_case("979 10 90636 07 1", "9791090636071" ; "spaces")]
+    fn parse_isbn(isbn: &str, expected: &str) -> Result<()> {
+        assert_eq!(expected, Isbn::parse(isbn)?.isbn13());
+        Ok(())
+    }
+
+    #[test_case("9780306406157", Some("0306406152"))]
+    #[test_case("9780804429573", Some("080442957X"))]
+    #[test_case("9791090636071", None ; "979 has no isbn 10")]
+    fn isbn10(isbn: &str, expected: Option<&str>) -> Result<()> {
+        assert_eq!(expected, Isbn::parse(isbn)?.isbn10().as_deref());
+        Ok(())
+    }
+
+    #[test_case("0-306-40615-3" ; "wrong isbn 10 check digit")]
+    #[test_case("978-0-306-40615-8" ; "wrong isbn 13 check digit")]
+    #[test_case("123-0-306-40615-7" ; "not a book")]
+    #[test_case("030640615" ; "too short")]
+    fn invalid_isbn(isbn: &str) {
+        assert!(matches!(
+            Isbn::parse(isbn),
+            Err(Error::InvalidArgument { .. })
+        ));
+    }
+
+    #[test_case("10.1145/3290605.3300233", "https://doi.org/10.1145/3290605.3300233" ; "doi")]
+    #[test_case("doi:10.1000/a?b#c", "https://doi.org/10.1000/a%3Fb%23c" ; "doi is escaped")]
+    #[test_case("2310.06825", "https://arxiv.org/abs/2310.06825" ; "arxiv")]
+    #[test_case("arXiv:1207.7214v2", "https://arxiv.org/abs/1207.7214v2" ; "arxiv prefix and version")]
+    #[test_case("hep-th/9901001", "https://arxiv.org/abs/hep-th/9901001" ; "old arxiv")]
+    #[test_case("math.GT/0309136", "https://arxiv.org/abs/math.GT/0309136" ; "old arxiv subject class")]
+    #[test_case("PMID: 31452104", "https://pubmed.ncbi.nlm.nih.gov/31452104/" ; "pmid")]
+    #[test_case("9780306406157", "https://www.booktopia.com.au/search.ep?keywords=9780306406157&productType=917504" ; "isbn 13")]
+    #[test_case("0-306-40615-2", "https://www.booktopia.com.au/search.ep?keywords=9780306406157&productType=917504" ; "isbn 10")]
+    #[test_case("ISBN 0306406152", "https://www.booktopia.com.au/search.ep?keywords=9780306406157&productType=917504" ; "isbn prefix")]
+    fn detect(query: &str, expected: &str) -> Result<()> {
+        let book_store = Template::parse(DEFAULT_BOOK_STORE)?;
+        let identifier = Identifier::detect(query)?.expect("an identifier");
+        assert_eq!(expected, identifier.url(&book_store)?.as_str());
+        Ok(())
+    }
+
+    #[test_case("802.11" ; "decimal")]
+    #[test_case("1205.12345" ; "arxiv with too many digits")]
+    #[test_case("2313.06825" ; "arxiv month")]
+    #[test_case("2300.06825" ; "arxiv month zero")]
+    #[test_case("9901.12345" ; "arxiv year that hasn't happened")]
+    #[test_case("0703.1234" ; "arxiv before new style ids")]
+    #[test_case("foo/9901001" ; "unknown arxiv archive")]
+    #[test_case("31452104" ; "pmid without prefix")]
+    #[test_case("0306406152" ; "unformatted isbn 10")]
+    #[test_case("9780306406158" ; "isbn with wrong check digit")]
+    fn not_identifiers(query: &str) -> Result<()> {
+        assert_eq!(None, Identifier::detect(query)?);
+        Ok(())
+    }
+
+    #[test]
+    fn arxiv_years() {
+        let year = current_year();
+        assert!(year >= 2024, "{}", year);
+        assert!(is_arxiv_date(year % 100, 1, 5));
+        assert!(!is_arxiv_date(year % 100 + 1, 1, 5));
+    }
+
+    #[test]
+    fn prefixed_isbn_with_wrong_check_digit() {
+        assert_eq!(
+            "Invalid argument \"978-0-306-40615-8\": the check digit should be 7",
+            Identifier::detect("isbn 978-0-306-40615-8")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
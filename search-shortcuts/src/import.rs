@@ -0,0 +1,39 @@
+//! Importers that turn keyword searches saved in other tools into registry
+//! entries, which can then be written out with [`Registry::to_toml`].
+
+#[cfg(feature = "chrome")]
+pub mod chrome;
+pub mod firefox;
+
+use crate::registry::Registry;
+
+use std::collections::HashSet;
+
+/// The result of an import.
+#[derive(Debug)]
+pub struct Import {
+    pub registry: Registry,
+    /// Imported keywords that are also used by the built-in shortcuts, so
+    /// one would shadow the other depending on the order they're added in.
+    pub shadowed: Vec<String>,
+    /// Entries that couldn't be imported, with the reason why.
+    pub skipped: Vec<String>,
+}
+
+impl Import {
+    fn new(registry: Registry, skipped: Vec<String>) -> Self {
+        let builtin = Registry::default();
+        let builtin: HashSet<_> = builtin.keywords().into_iter().collect();
+        let shadowed = registry
+            .keywords()
+            .into_iter()
+            .filter(|keyword| builtin.contains(keyword))
+            .map(str::to_string)
+            .collect();
+        Self {
+            registry,
+            shadowed,
+            skipped,
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Imports custom search engines from Chrome's `Web Data` SQLite database,
+//! found in the profile directory, eg `~/.config/google-chrome/Default/`.
+//!
+//! Chrome keeps the database locked while it's running, so import from a
+//! copy of the file. Search engines are stored in the `keywords` table with
+//! `{searchTerms}` in the url where the search terms go.
+
+use super::Import;
+use crate::errors::Result;
+use crate::registry::Registry;
+use crate::template::Template;
+
+use rusqlite::{Connection, OpenFlags};
+use std::collections::HashSet;
+use std::path::Path;
+use url::Url;
+
+const MARKERS: &[(&str, Option<&str>)] = &[("{searchTerms}", None)];
+
+/// Placeholders Chrome fills in with fixed values.
+const ENCODINGS: &[&str] = &["{inputEncoding}", "{outputEncoding}"];
+
+pub fn import(path: impl AsRef<Path>) -> Result<Import> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    import_from(&connection)
+}
+
+fn import_from(connection: &Connection) -> Result<Import> {
+    let mut statement =
+        connection.prepare("SELECT keyword, short_name, url FROM keywords ORDER BY id")?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+        ))
+    })?;
+
+    let mut registry = Registry::empty();
+    let mut skipped = Vec::new();
+    let mut seen = HashSet::new();
+    for row in rows {
+        let (keyword, name, url) = row?;
+        if keyword.is_empty() {
+            continue;
+        }
+        if !seen.insert(keyword.clone()) {
+            skipped.push(format!("{}: duplicate keyword", keyword));
+            continue;
+        }
+        let url = ENCODINGS
+            .iter()
+            .fold(url, |url, encoding| url.replace(encoding, "UTF-8"));
+        if url.replace(MARKERS[0].0, "").contains('{') {
+            // eg {google:baseURL} in the engines Chrome ships with
+            skipped.push(format!("{}: uses Chrome specific placeholders", keyword));
+            continue;
+        }
+        if url.contains(MARKERS[0].0) {
+            match Template::from_markers(&url, MARKERS) {
+                Ok(template) => registry.add_template(&keyword, &name, template),
+                Err(err) => skipped.push(format!("{}: {}", keyword, err)),
+            }
+        } else {
+            match Url::parse(&url) {
+                Ok(url) => registry.add_redirect(&keyword, &name, url),
+                Err(err) => skipped.push(format!("{}: {}", keyword, err)),
+            }
+        }
+    }
+    Ok(Import::new(registry, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Resolver;
+    use test_case::test_case;
+
+    /// The columns of Chrome's `keywords` table that matter here.
+    const WEB_DATA: &str = r#"
+        CREATE TABLE keywords (id INTEGER PRIMARY KEY, short_name VARCHAR NOT NULL, keyword VARCHAR NOT NULL, favicon_url VARCHAR NOT NULL, url VARCHAR NOT NULL);
+        INSERT INTO keywords VALUES (2, 'Google', 'google.com', '', '{google:baseURL}search?q={searchTerms}&{google:RLZ}ie={inputEncoding}');
+        INSERT INTO keywords VALUES (3, 'Jira', 'jira', '', 'https://jira.example.com/browse/{searchTerms}');
+        INSERT INTO keywords VALUES (4, 'Wiki', 'wiki', '', 'https://wiki.example.com/search?q={searchTerms}&ie={inputEncoding}');
+        INSERT INTO keywords VALUES (5, 'Grafana', 'graf', '', 'https://grafana.example.com/');
+        INSERT INTO keywords VALUES (6, 'Code search', 'gh', '', 'https://github.com/search?q={searchTerms}&type=code');
+        INSERT INTO keywords VALUES (7, 'Duplicate', 'jira', '', 'https://example.com/other/{searchTerms}');
+        INSERT INTO keywords VALUES (8, 'No keyword', '', '', 'https://example.com/{searchTerms}');
+    "#;
+
+    fn web_data() -> Result<Import> {
+        let connection = Connection::open_in_memory()?;
+        connection.execute_batch(WEB_DATA)?;
+        import_from(&connection)
+    }
+
+    #[test]
+    fn import_web_data() -> Result<()> {
+        let import = web_data()?;
+        assert_eq!(
+            vec!["graf", "jira", "wiki", "gh"],
+            import.registry.keywords()
+        );
+        assert_eq!(vec!["gh"], import.shadowed);
+        assert_eq!(
+            vec![
+                "google.com: uses Chrome specific placeholders",
+                "jira: duplicate keyword"
+            ],
+            import.skipped
+        );
+        Ok(())
+    }
+
+    #[test_case("https://jira.example.com/browse/ABC-123", "jira ABC-123")]
+    #[test_case("https://wiki.example.com/search?q=on+call&ie=UTF-8", "wiki on call")]
+    #[test_case("https://grafana.example.com/", "graf")]
+    fn resolve_imported(expected: &str, query: &str) -> Result<()> {
+        let resolver = Resolver::builder().registry(web_data()?.registry).build();
+        assert_eq!(expected, resolver.resolve(query)?.url.as_str());
+        Ok(())
+    }
+}
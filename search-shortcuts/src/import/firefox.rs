@@ -0,0 +1,233 @@
+//! Imports keyword bookmarks from a Firefox bookmarks HTML export (the
+//! Netscape bookmark format), eg
+//!
+//! ```html
+//! <DT><A HREF="https://example.com/?q=%s" SHORTCUTURL="ex">Example</A>
+//! ```
+//!
+//! Firefox replaces `%s` with the encoded search terms and `%S` with them
+//! as typed.
+
+use super::Import;
+use crate::registry::Registry;
+use crate::template::Template;
+
+use std::collections::HashSet;
+use url::Url;
+
+const MARKERS: &[(&str, Option<&str>)] = &[("%s", None), ("%S", Some("raw"))];
+
+#[derive(Debug, Default)]
+struct Bookmark {
+    href: String,
+    keyword: String,
+    title: String,
+    post_data: bool,
+}
+
+pub fn import(html: &str) -> Import {
+    let mut registry = Registry::empty();
+    let mut skipped = Vec::new();
+    let mut seen = HashSet::new();
+    for bookmark in bookmarks(html) {
+        if bookmark.keyword.is_empty() {
+            continue;
+        }
+        if bookmark.post_data {
+            skipped.push(format!("{}: uses POST data", bookmark.keyword));
+            continue;
+        }
+        if !seen.insert(bookmark.keyword.clone()) {
+            skipped.push(format!("{}: duplicate keyword", bookmark.keyword));
+            continue;
+        }
+        if bookmark.href.contains("%s") || bookmark.href.contains("%S") {
+            match Template::from_markers(&bookmark.href, MARKERS) {
+                Ok(template) => registry.add_template(&bookmark.keyword, &bookmark.title, template),
+                Err(err) => skipped.push(format!("{}: {}", bookmark.keyword, err)),
+            }
+        } else {
+            match Url::parse(&bookmark.href) {
+                Ok(url) => registry.add_redirect(&bookmark.keyword, &bookmark.title, url),
+                Err(err) => skipped.push(format!("{}: {}", bookmark.keyword, err)),
+            }
+        }
+    }
+    Import::new(registry, skipped)
+}
+
+/// Finds every `<A ...>title</A>` in the export.
+fn bookmarks(html: &str) -> Vec<Bookmark> {
+    let mut bookmarks = Vec::new();
+    let mut rest = html;
+    while let Some(start) = find_ignore_case(rest, "<a ") {
+        rest = &rest[start + 3..];
+        let (attributes, after_tag) = split_tag(rest);
+        let mut bookmark = Bookmark::default();
+        for (name, value) in parse_attributes(attributes) {
+            match name.to_ascii_uppercase().as_str() {
+                "HREF" => bookmark.href = value,
+                "SHORTCUTURL" => bookmark.keyword = value,
+                "POST_DATA" => bookmark.post_data = true,
+                _ => {}
+            }
+        }
+        let end = find_ignore_case(after_tag, "</a>").unwrap_or(after_tag.len());
+        bookmark.title = decode_entities(after_tag[..end].trim());
+        bookmarks.push(bookmark);
+        rest = after_tag;
+    }
+    bookmarks
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Splits at the `>` that ends the tag, skipping any inside quoted values.
+fn split_tag(tag: &str) -> (&str, &str) {
+    let mut quoted = false;
+    for (i, c) in tag.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '>' if !quoted => return (&tag[..i], &tag[i + 1..]),
+            _ => {}
+        }
+    }
+    (tag, "")
+}
+
+fn parse_attributes(attributes: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut rest = attributes.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        rest = rest[name_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, after) = match after.strip_prefix('"') {
+                    Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+                    None => after.split_once(char::is_whitespace).unwrap_or((after, "")),
+                };
+                rest = after.trim_start();
+                decode_entities(value)
+            }
+            None => String::new(),
+        };
+        if !name.is_empty() {
+            parsed.push((name.to_string(), value));
+        }
+    }
+    parsed
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .find(';')
+            .map(|end| (&rest[1..end], end))
+            .and_then(|(entity, end)| {
+                let c = match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    _ => entity
+                        .strip_prefix("#x")
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .or_else(|| entity.strip_prefix('#')?.parse().ok())
+                        .and_then(char::from_u32),
+                };
+                c.map(|c| (c, end))
+            });
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Result;
+    use crate::Resolver;
+    use test_case::test_case;
+
+    const BOOKMARKS: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file. -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks Menu</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1600000000" LAST_MODIFIED="1600000000">Searches</H3>
+    <DL><p>
+        <DT><A HREF="https://jira.example.com/browse/%s" ADD_DATE="1600000000" SHORTCUTURL="jira">Jira &amp; friends</A>
+        <DT><A HREF="https://wiki.example.com/search?q=%s&amp;space=ENG" ADD_DATE="1600000000" SHORTCUTURL="wiki">Wiki</A>
+        <dt><a href="https://grafana.example.com/" shortcuturl="graf">Grafana</a>
+        <DT><A HREF="https://example.com/raw/%S" SHORTCUTURL="raw">Raw</A>
+        <DT><A HREF="https://example.com/post" SHORTCUTURL="post" POST_DATA="q=%s">Post</A>
+        <DT><A HREF="https://github.com/search?q=%s&amp;type=code" SHORTCUTURL="gh">Code search</A>
+        <DT><A HREF="https://example.com/other/%s" SHORTCUTURL="jira">Duplicate</A>
+        <DT><A HREF="https://www.rust-lang.org/">No keyword</A>
+    </DL><p>
+</DL>
+"#;
+
+    #[test]
+    fn import_bookmarks() {
+        let import = import(BOOKMARKS);
+        assert_eq!(
+            vec!["graf", "jira", "wiki", "raw", "gh"],
+            import.registry.keywords()
+        );
+        assert_eq!(vec!["gh"], import.shadowed);
+        assert_eq!(
+            vec!["post: uses POST data", "jira: duplicate keyword"],
+            import.skipped
+        );
+    }
+
+    #[test_case("https://jira.example.com/browse/ABC-123", "jira ABC-123")]
+    #[test_case("https://wiki.example.com/search?q=on+call&space=ENG", "wiki on call")]
+    #[test_case("https://grafana.example.com/", "graf")]
+    #[test_case("https://example.com/raw/a/b", "raw a/b")]
+    fn resolve_imported(expected: &str, query: &str) -> Result<()> {
+        let resolver = Resolver::builder()
+            .registry(import(BOOKMARKS).registry)
+            .build();
+        assert_eq!(expected, resolver.resolve(query)?.url.as_str());
+        Ok(())
+    }
+
+    #[test]
+    fn descriptions_are_decoded() -> Result<()> {
+        let toml = import(BOOKMARKS).registry.to_toml()?;
+        assert!(
+            toml.contains(r#"description = "Jira & friends""#),
+            "{}",
+            toml
+        );
+        Ok(())
+    }
+}
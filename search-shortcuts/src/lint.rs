@@ -0,0 +1,375 @@
+//! Checks for shortcuts that can't be reached, usually because a config
+//! change reused a keyword or added one that starts with another.
+//!
+//! Shortcuts are checked in the order the resolver tries them. Keywords that
+//! take arguments and keywords that don't are matched separately, so a
+//! redirect and a template sharing a keyword (eg `bt` and `bt lol donkey`)
+//! is fine. Regexes can't be compared, so patterns are checked by resolving
+//! their examples.
+
+use crate::explain::Rule;
+use crate::normalize::{same_keyword, strip_prefix_ignore_case};
+use crate::resolver::Resolver;
+use crate::shortcut::{Arguments, Shortcut};
+
+use std::fmt;
+
+/// A problem with the order or keywords of a resolver's shortcuts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lint {
+    /// A keyword that a shortcut lists more than once.
+    DuplicateAlias { keyword: String, shortcut: String },
+    /// A keyword that an earlier shortcut already uses.
+    DuplicateKeyword {
+        keyword: String,
+        shortcut: String,
+        earlier: String,
+    },
+    /// A keyword and separator that always start with an earlier shortcut's
+    /// keyword and separator, eg `docs/` after a shortcut with `d` and `o`.
+    ShadowedKeyword {
+        prefix: String,
+        shortcut: String,
+        by: String,
+        earlier: String,
+    },
+    /// A shortcut that none of its keywords can reach.
+    Unreachable { shortcut: String },
+    /// A pattern's example that a keyword or another pattern gets to first.
+    OverlappingPattern {
+        pattern: String,
+        example: String,
+        by: String,
+    },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateAlias { keyword, shortcut } => {
+                write!(f, "`{}` is listed twice by {}", keyword, shortcut)
+            }
+            Self::DuplicateKeyword {
+                keyword,
+                shortcut,
+                earlier,
+            } => write!(
+                f,
+                "`{}` of {} is already used by an earlier shortcut, {}",
+                keyword, shortcut, earlier
+            ),
+            Self::ShadowedKeyword {
+                prefix,
+                shortcut,
+                by,
+                earlier,
+            } => write!(
+                f,
+                "queries starting with `{}` go to {} (as they start with `{}`) instead of {}",
+                prefix, earlier, by, shortcut
+            ),
+            Self::Unreachable { shortcut } => write!(f, "{} can never be used", shortcut),
+            Self::OverlappingPattern {
+                pattern,
+                example,
+                by,
+            } => write!(
+                f,
+                "`{}`, an example of pattern `{}`, goes to {} instead",
+                example, pattern, by
+            ),
+        }
+    }
+}
+
+/// How a shortcut is named in lints: its first keyword and description.
+fn name(shortcut: &dyn Shortcut) -> String {
+    let keyword = shortcut.keywords().first().copied().unwrap_or_default();
+    match shortcut.description() {
+        "" => format!("`{}`", keyword),
+        description => format!("`{}` ({})", keyword, description.trim_end_matches('.')),
+    }
+}
+
+/// One way a shortcut can be matched: a keyword alone, or a keyword and
+/// separator at the start of the query.
+struct Matcher<'a> {
+    shortcut: usize,
+    keyword: &'a str,
+    separator: Option<&'a str>,
+}
+
+impl Matcher<'_> {
+    fn prefix(&self) -> String {
+        format!("{}{}", self.keyword, self.separator.unwrap_or_default())
+    }
+
+    /// Whether every query this matches is matched by `earlier` instead.
+    fn is_shadowed_by(&self, earlier: &Matcher) -> bool {
+        match (self.separator, earlier.separator) {
+            (None, None) => same_keyword(self.keyword, earlier.keyword),
+            (Some(_), Some(_)) => {
+                let (prefix, earlier_prefix) = (self.prefix(), earlier.prefix());
+                // an alias starting with another of the same shortcut's
+                // aliases still goes to the right place
+                strip_prefix_ignore_case(&prefix, &earlier_prefix)
+                    .is_some_and(|rest| rest.is_empty() || self.shortcut != earlier.shortcut)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn matchers<'a>(shortcuts: &[&'a dyn Shortcut]) -> Vec<Matcher<'a>> {
+    let mut matchers = Vec::new();
+    for (index, shortcut) in shortcuts.iter().enumerate() {
+        for keyword in shortcut.keywords() {
+            if shortcut.arguments() != Arguments::Required {
+                matchers.push(Matcher {
+                    shortcut: index,
+                    keyword,
+                    separator: None,
+                });
+            }
+            if shortcut.arguments() == Arguments::None {
+                continue;
+            }
+            for separator in shortcut.separators() {
+                matchers.push(Matcher {
+                    shortcut: index,
+                    keyword,
+                    separator: Some(separator),
+                });
+            }
+        }
+    }
+    matchers
+}
+
+/// Lists the keywords and shortcuts in the resolver that can't be reached.
+pub fn lint(resolver: &Resolver) -> Vec<Lint> {
+    let shortcuts: Vec<_> = resolver.shortcuts().collect();
+    let matchers = matchers(&shortcuts);
+    let mut lints = Vec::new();
+    let mut reachable = vec![false; shortcuts.len()];
+    for (i, matcher) in matchers.iter().enumerate() {
+        let shortcut = name(shortcuts[matcher.shortcut]);
+        let earlier = match matchers[..i].iter().find(|e| matcher.is_shadowed_by(e)) {
+            Some(earlier) => earlier,
+            None => {
+                reachable[matcher.shortcut] = true;
+                continue;
+            }
+        };
+        // an alias repeated by its own shortcut still goes to the right place
+        if earlier.shortcut == matcher.shortcut {
+            reachable[matcher.shortcut] = true;
+        }
+        let lint = if earlier.shortcut == matcher.shortcut {
+            Lint::DuplicateAlias {
+                keyword: matcher.keyword.to_string(),
+                shortcut,
+            }
+        } else if same_keyword(matcher.keyword, earlier.keyword) {
+            Lint::DuplicateKeyword {
+                keyword: matcher.keyword.to_string(),
+                shortcut,
+                earlier: name(shortcuts[earlier.shortcut]),
+            }
+        } else {
+            Lint::ShadowedKeyword {
+                prefix: matcher.prefix(),
+                shortcut,
+                by: earlier.prefix(),
+                earlier: name(shortcuts[earlier.shortcut]),
+            }
+        };
+        // a keyword with several separators is only reported once
+        if !lints.contains(&lint) {
+            lints.push(lint);
+        }
+    }
+    for (shortcut, reachable) in shortcuts.iter().zip(reachable) {
+        if !reachable {
+            lints.push(Lint::Unreachable {
+                shortcut: name(*shortcut),
+            });
+        }
+    }
+    lints.extend(lint_patterns(resolver));
+    lints
+}
+
+fn lint_patterns(resolver: &Resolver) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    for pattern in resolver.patterns() {
+        for example in pattern.examples() {
+            // examples that don't resolve at all are for `validate` to report
+            let resolution = match resolver.resolve(example) {
+                Ok(resolution) => resolution,
+                Err(_) => continue,
+            };
+            let by = match (resolution.kind, resolution.shortcut) {
+                (Rule::Pattern, Some(name)) if name == pattern.name() => continue,
+                (Rule::Pattern, Some(name)) => format!("pattern `{}`", name),
+                (kind, Some(keyword)) => format!("`{}` ({})", keyword, kind),
+                (kind, None) => format!("the {} rule", kind),
+            };
+            lints.push(Lint::OverlappingPattern {
+                pattern: pattern.name().to_string(),
+                example: example.to_string(),
+                by,
+            });
+        }
+    }
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Registry;
+
+    fn lint_toml(config: &str) -> Vec<String> {
+        let registry = Registry::from_toml(config).unwrap();
+        let resolver = Resolver::builder().registry(registry).build();
+        lint(&resolver).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn default_config_is_clean() {
+        assert_eq!(Vec::<Lint>::new(), lint(&Resolver::default()));
+    }
+
+    #[test]
+    fn redirect_and_template_can_share_a_keyword() {
+        let config = r#"
+            [[redirect]]
+            keyword = "bt"
+            url = "https://www.booktopia.com.au/"
+
+            [[shortcut]]
+            keyword = "bt"
+            template = "https://www.booktopia.com.au/search.ep?keywords={rest}"
+        "#;
+        assert_eq!(Vec::<String>::new(), lint_toml(config));
+    }
+
+    #[test]
+    fn duplicate_keywords() {
+        let config = r#"
+            [[redirect]]
+            keyword = "wiki"
+            aliases = ["kb", "KB"]
+            url = "https://wiki.example.com/"
+
+            [[redirect]]
+            keyword = "kb"
+            description = "Knowledge base."
+            url = "https://kb.example.com/"
+        "#;
+        assert_eq!(
+            vec![
+                "`KB` is listed twice by `wiki`",
+                "`kb` of `kb` (Knowledge base) is already used by an earlier shortcut, `wiki`",
+                "`kb` (Knowledge base) can never be used",
+            ],
+            lint_toml(config)
+        );
+    }
+
+    #[test]
+    fn shadowed_keywords() {
+        let config = r#"
+            [[shortcut]]
+            keyword = "jira"
+            template = "https://jira.example.com/browse/?q={rest}"
+
+            [[shortcut]]
+            keyword = "d"
+            separators = ["o"]
+            template = "https://d.example.com/?q={rest}"
+
+            [[shortcut]]
+            keyword = "docs"
+            description = "Crate docs."
+            handler = "docs"
+
+            [[shortcut]]
+            keyword = "jira"
+            aliases = ["j"]
+            template = "https://jira.example.com/?q={rest}"
+        "#;
+        assert_eq!(
+            vec![
+                "queries starting with `docs ` go to `d` (as they start with `do`) instead of `docs` (Crate docs)",
+                "queries starting with `docs/` go to `d` (as they start with `do`) instead of `docs` (Crate docs)",
+                "`jira` of `jira` is already used by an earlier shortcut, `jira`",
+                "`docs` (Crate docs) can never be used",
+            ],
+            lint_toml(config)
+        );
+    }
+
+    #[test]
+    fn optional_arguments() {
+        let config = r#"
+            [[redirect]]
+            keyword = "x"
+            url = "https://xkcd.com/"
+
+            [[shortcut]]
+            keyword = "x"
+            description = "A comic by number."
+            template = "https://xkcd.com/{1}/"
+            url = "https://xkcd.com/"
+
+            [[shortcut]]
+            keyword = "x"
+            description = "Somewhere else."
+            template = "https://example.com/{1}/"
+        "#;
+        assert_eq!(
+            vec![
+                "`x` of `x` (A comic by number) is already used by an earlier shortcut, `x`",
+                "`x` of `x` (Somewhere else) is already used by an earlier shortcut, `x` (A comic by number)",
+                "`x` (Somewhere else) can never be used",
+            ],
+            lint_toml(config)
+        );
+    }
+
+    #[test]
+    fn overlapping_patterns() {
+        let config = r#"
+            [[shortcut]]
+            keyword = "rfc"
+            template = "https://example.com/rfc/{rest}"
+
+            [[pattern]]
+            name = "ticket"
+            examples = ["ABC-123"]
+            pattern = '[A-Z]+-[\d-]+'
+            template = "https://tickets.example.com/{rest}"
+
+            [[pattern]]
+            name = "cve"
+            examples = ["CVE-2024-3094"]
+            pattern = 'CVE-\d{4}-\d+'
+            template = "https://cve.example.com/{rest}"
+
+            [[pattern]]
+            name = "rfc"
+            examples = ["rfc 9110", "RFC9110"]
+            pattern = '(?i)rfc ?(\d+)'
+            template = "https://rfc.example.com/{1}"
+        "#;
+        assert_eq!(
+            vec![
+                "`CVE-2024-3094`, an example of pattern `cve`, goes to pattern `ticket` instead",
+                "`rfc 9110`, an example of pattern `rfc`, goes to `rfc` (keyword) instead",
+            ],
+            lint_toml(config)
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Cleaning up queries before they're resolved, mostly for what phone
+//! keyboards produce: full-width characters, non-breaking spaces and curly
+//! quotes.
+//!
+//! The case of the query is left alone as arguments such as repository paths
+//! or tracking numbers can depend on it, keywords are matched ignoring case
+//! instead.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Whether the character is in the Halfwidth and Fullwidth Forms block, eg
+/// `ｇ` from a Japanese or Chinese keyboard.
+fn is_width_form(c: char) -> bool {
+    ('\u{FF01}'..='\u{FFEE}').contains(&c)
+}
+
+/// Normalises the full-width and half-width characters in a query with NFKC,
+/// folds curly quotes into straight ones and collapses each run of whitespace
+/// (including ideographic spaces) into a single space.
+///
+/// The rest of the query isn't normalised as that would change what's being
+/// searched for, eg `H₂O` would become `H2O`.
+pub(crate) fn normalize(query: &str) -> String {
+    let mut folded = String::with_capacity(query.len());
+    let mut run = String::new();
+    for c in query.chars() {
+        if is_width_form(c) {
+            // whole runs so half-width kana keep their voicing marks
+            run.push(c);
+            continue;
+        }
+        folded.extend(run.nfkc());
+        run.clear();
+        folded.push(c);
+    }
+    folded.extend(run.nfkc());
+
+    let mut normalized = String::with_capacity(folded.len());
+    let mut in_whitespace = false;
+    for c in folded.chars() {
+        if c.is_whitespace() {
+            if !in_whitespace {
+                normalized.push(' ');
+            }
+            in_whitespace = true;
+            continue;
+        }
+        in_whitespace = false;
+        normalized.push(match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => '\'',
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => '"',
+            c => c,
+        });
+    }
+    normalized
+}
+
+/// Strips `prefix` from the start of `s` ignoring case, eg for matching the
+/// keyword in `Gh rust-lang/rust`.
+pub(crate) fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let mut chars = s.chars();
+    for expected in prefix.chars() {
+        if !chars.next()?.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.as_str())
+}
+
+/// Whether two keywords are the same ignoring case.
+pub(crate) fn same_keyword(a: &str, b: &str) -> bool {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("gh foo", "gh foo" ; "unchanged")]
+    #[test_case("gh  foo", "gh foo" ; "double space")]
+    #[test_case("gh\tfoo", "gh foo" ; "tab")]
+    #[test_case("gh\u{a0}foo", "gh foo" ; "non-breaking space")]
+    #[test_case("gh\u{3000}foo", "gh foo" ; "ideographic space")]
+    #[test_case("ｇｈ ｆｏｏ", "gh foo" ; "full width")]
+    #[test_case("w \u{201C}don\u{2019}t panic\u{201D}", "w \"don't panic\"" ; "smart quotes")]
+    #[test_case("ｶﾞｲﾄﾞ", "ガイド" ; "half width kana")]
+    #[test_case("Donkey ", "Donkey " ; "keeps case and a trailing space")]
+    #[test_case("w H₂O", "w H₂O" ; "subscript")]
+    #[test_case("E=mc²", "E=mc²" ; "superscript")]
+    #[test_case("ｗ ½ ﬁle", "w ½ ﬁle" ; "fractions and ligatures")]
+    fn normalizes(query: &str, expected: &str) {
+        assert_eq!(expected, normalize(query));
+    }
+
+    #[test_case("Gh foo", "gh", Some(" foo"))]
+    #[test_case("DOCS/tokio", "docs", Some("/tokio"))]
+    #[test_case("g", "gh", None ; "too short")]
+    #[test_case("so foo", "gh", None ; "different keyword")]
+    fn strips_prefix(s: &str, prefix: &str, expected: Option<&str>) {
+        assert_eq!(expected, strip_prefix_ignore_case(s, prefix));
+    }
+}
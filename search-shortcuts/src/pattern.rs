@@ -0,0 +1,198 @@
+//! Shortcuts triggered by what the query looks like rather than a keyword,
+//! eg pasting `E0382` or `CVE-2024-3094`.
+//!
+//! A pattern is a regex that has to match the whole query (ignoring
+//! surrounding whitespace). Its url template is rendered with the regex's
+//! captures: `{1}`, `{2}`, ... are the numbered groups, `{name}` is the group
+//! named `name` and `{rest}` is the whole query.
+
+use crate::errors::{Error, Result};
+use crate::query::ParsedQuery;
+use crate::template::Template;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// When a pattern is tried, relative to the other rules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Before any keyword, for patterns that should win even if the query
+    /// starts with a keyword.
+    First,
+    /// After keywords and bangs but before the query is checked for a url,
+    /// domain or IP address.
+    BeforeDomain,
+    /// After every other rule, just before typo correction and the fallback
+    /// search engine.
+    #[default]
+    Last,
+}
+
+/// A regex matched against the whole query.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+struct WholeQuery {
+    source: String,
+    regex: Regex,
+}
+
+impl TryFrom<String> for WholeQuery {
+    type Error = Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        let regex = Regex::new(&format!("^(?:{})$", source)).map_err(|err| {
+            Error::InvalidConfig(format!("invalid pattern {:?}: {}", source, err))
+        })?;
+        Ok(Self { source, regex })
+    }
+}
+
+impl From<WholeQuery> for String {
+    fn from(pattern: WholeQuery) -> Self {
+        pattern.source
+    }
+}
+
+/// A rule that sends any query matching a regex to a url template.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pattern {
+    /// Identifies the pattern in explanations and lints, like a keyword.
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    description: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    category: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    examples: Vec<String>,
+    pattern: WholeQuery,
+    template: Template,
+    #[serde(default)]
+    priority: Priority,
+}
+
+impl Pattern {
+    pub fn new(name: &str, description: &str, pattern: &str, template: Template) -> Result<Self> {
+        let pattern = Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            category: String::new(),
+            examples: Vec::new(),
+            pattern: WholeQuery::try_from(pattern.to_string())?,
+            template,
+            priority: Priority::default(),
+        };
+        pattern.validate()?;
+        Ok(pattern)
+    }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
+    pub fn examples(&self) -> Vec<&str> {
+        self.examples.iter().map(String::as_str).collect()
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// The query split into the regex's captures, if it matches.
+    pub fn captures(&self, query: &str) -> Option<ParsedQuery> {
+        let query = query.trim();
+        let captures = self.pattern.regex.captures(query)?;
+        let value = |capture: Option<regex::Match>| {
+            capture.map_or_else(String::new, |capture| capture.as_str().to_string())
+        };
+        Some(ParsedQuery {
+            keyword: String::new(),
+            args: captures.iter().skip(1).map(value).collect(),
+            raw_rest: query.to_string(),
+            modifiers: self
+                .pattern
+                .regex
+                .capture_names()
+                .flatten()
+                .map(|name| (name.to_string(), value(captures.name(name))))
+                .collect(),
+        })
+    }
+
+    pub fn render(&self, captures: &ParsedQuery) -> Result<Url> {
+        self.template.render(captures)
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            return Err(Error::InvalidConfig("pattern name is empty".into()));
+        }
+        let regex = &self.pattern.regex;
+        for name in self.template.placeholders() {
+            let known = match name.parse::<usize>() {
+                Ok(index) => index < regex.captures_len(),
+                Err(_) => {
+                    matches!(name, "rest" | "query")
+                        || regex.capture_names().flatten().any(|n| n == name)
+                }
+            };
+            if !known {
+                return Err(Error::InvalidConfig(format!(
+                    "pattern {:?} has no capture for {{{}}}",
+                    self.name, name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn pattern(pattern: &str, template: &str) -> Result<Pattern> {
+        Pattern::new("test", "", pattern, Template::parse(template)?)
+    }
+
+    #[test_case(r"(?i)(?P<code>e\d{4})", "https://example.com/{code|upper}", "e0382", Some("https://example.com/E0382") ; "named capture")]
+    #[test_case(r"rfc ?(\d+)", "https://example.com/rfc{1|path}", " rfc 9110 ", Some("https://example.com/rfc9110") ; "numbered capture")]
+    #[test_case(r"r/\w+", "https://example.com/{rest|path}", "r/rust", Some("https://example.com/r%2Frust") ; "whole query")]
+    #[test_case(r"E\d{4}", "https://example.com/{rest}", "see E0382", None ; "must match the whole query")]
+    #[test_case(r"(a)|(b)", "https://example.com/{1}{2}", "b", Some("https://example.com/b") ; "unmatched group is empty")]
+    fn captures(regex: &str, template: &str, query: &str, expected: Option<&str>) -> Result<()> {
+        let pattern = pattern(regex, template)?;
+        let url = pattern
+            .captures(query)
+            .map(|captures| pattern.render(&captures))
+            .transpose()?;
+        assert_eq!(expected, url.as_ref().map(Url::as_str));
+        Ok(())
+    }
+
+    #[test_case(r"(", "https://example.com/" ; "invalid regex")]
+    #[test_case(r"E\d{4}", "https://example.com/{code}" ; "unknown capture name")]
+    #[test_case(r"(E)\d{4}", "https://example.com/{2}" ; "unknown capture number")]
+    fn invalid(regex: &str, template: &str) {
+        assert!(matches!(
+            pattern(regex, template),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+}
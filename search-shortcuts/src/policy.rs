@@ -0,0 +1,103 @@
+//! Enterprise browser policies that install a deployment as the default
+//! search engine, for rolling it out without adding `osdf.xml` by hand.
+
+use crate::errors::Result;
+
+use serde_json::{json, Value};
+use url::Url;
+
+/// The name the engine is installed under, matching `osdf.xml`.
+pub const ENGINE_NAME: &str = "shortcuts";
+const DESCRIPTION: &str = "search shortcuts";
+
+/// The url browsers send searches to, eg
+/// `https://search.example.com/?q={searchTerms}`.
+pub fn search_url(base_url: &Url) -> Result<String> {
+    // {searchTerms} is the placeholder both browsers use, and braces are left
+    // alone in the query so it comes through as is
+    Ok(base_url.join("?q={searchTerms}")?.to_string())
+}
+
+/// The url browsers fetch search suggestions from.
+pub fn suggest_url(base_url: &Url) -> Result<String> {
+    Ok(base_url.join("suggest?q={searchTerms}")?.to_string())
+}
+
+/// A Firefox `policies.json` that adds the engine and makes it the default.
+///
+/// Firefox only applies `SearchEngines` policies on ESR builds.
+pub fn firefox(base_url: &Url) -> Result<Value> {
+    Ok(json!({
+        "policies": {
+            "SearchEngines": {
+                "Add": [{
+                    "Name": ENGINE_NAME,
+                    "Description": DESCRIPTION,
+                    "URLTemplate": search_url(base_url)?,
+                    "Method": "GET",
+                    "SuggestURLTemplate": suggest_url(base_url)?,
+                }],
+                "Default": ENGINE_NAME,
+            }
+        }
+    }))
+}
+
+/// The Chrome `DefaultSearchProvider*` policies that make the engine the
+/// default, as a JSON managed policy file.
+pub fn chrome(base_url: &Url) -> Result<Value> {
+    Ok(json!({
+        "DefaultSearchProviderEnabled": true,
+        "DefaultSearchProviderName": ENGINE_NAME,
+        "DefaultSearchProviderKeyword": ENGINE_NAME,
+        "DefaultSearchProviderSearchURL": search_url(base_url)?,
+        "DefaultSearchProviderSuggestURL": suggest_url(base_url)?,
+        "DefaultSearchProviderEncodings": ["UTF-8"],
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(
+        "https://search.example.com",
+        "https://search.example.com/?q={searchTerms}"
+    )]
+    #[test_case(
+        "https://example.com/shortcuts/",
+        "https://example.com/shortcuts/?q={searchTerms}"
+    )]
+    #[test_case(
+        "https://example.com/?q=old",
+        "https://example.com/?q={searchTerms}" ; "replaces query"
+    )]
+    fn search_urls(base_url: &str, expected: &str) -> Result<()> {
+        assert_eq!(expected, search_url(&Url::parse(base_url)?)?);
+        Ok(())
+    }
+
+    #[test]
+    fn policies() -> Result<()> {
+        let base_url = Url::parse("https://search.example.com/")?;
+        let firefox = firefox(&base_url)?;
+        let engines = &firefox["policies"]["SearchEngines"];
+        assert_eq!("shortcuts", engines["Default"]);
+        assert_eq!(
+            "https://search.example.com/?q={searchTerms}",
+            engines["Add"][0]["URLTemplate"]
+        );
+        let chrome = chrome(&base_url)?;
+        assert_eq!(true, chrome["DefaultSearchProviderEnabled"]);
+        assert_eq!(
+            "https://search.example.com/?q={searchTerms}",
+            chrome["DefaultSearchProviderSearchURL"]
+        );
+        assert_eq!(
+            "https://search.example.com/suggest?q={searchTerms}",
+            chrome["DefaultSearchProviderSuggestURL"]
+        );
+        Ok(())
+    }
+}
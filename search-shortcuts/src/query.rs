@@ -0,0 +1,161 @@
+/// A query split into the keyword that selected a shortcut and the arguments
+/// that follow it.
+///
+/// Arguments are separated by whitespace and may be quoted with `"` or `'` to
+/// include spaces. Unquoted `key:value` arguments are collected as modifiers
+/// instead of positional arguments, eg `gh lang:rust "async runtime"` has one
+/// argument (`async runtime`) and one modifier (`lang` = `rust`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedQuery {
+    /// The keyword (or alias) that selected the shortcut.
+    pub keyword: String,
+    /// The positional arguments, with any quotes removed.
+    pub args: Vec<String>,
+    /// Everything after the keyword and separator, untouched.
+    pub raw_rest: String,
+    /// The `key:value` arguments in the order they appeared.
+    pub modifiers: Vec<(String, String)>,
+}
+
+impl ParsedQuery {
+    /// Tokenizes everything after the keyword.
+    pub fn new(keyword: &str, rest: &str) -> Self {
+        let mut args = Vec::new();
+        let mut modifiers = Vec::new();
+        for token in tokenize(rest) {
+            match token.modifier() {
+                Some((key, value)) => modifiers.push((key.to_string(), value.to_string())),
+                None => args.push(token.text),
+            }
+        }
+        Self {
+            keyword: keyword.to_string(),
+            args,
+            raw_rest: rest.to_string(),
+            modifiers,
+        }
+    }
+
+    /// Parses a whole query, treating the first word as the keyword.
+    pub fn parse(query: &str) -> Self {
+        let query = query.trim_start();
+        match query.split_once(char::is_whitespace) {
+            Some((keyword, rest)) => Self::new(keyword, rest.trim_start()),
+            None => Self::new(query, ""),
+        }
+    }
+
+    pub fn arg(&self, index: usize) -> Option<&str> {
+        self.args.get(index).map(String::as_str)
+    }
+
+    /// The value of the last modifier with this key.
+    pub fn modifier(&self, key: &str) -> Option<&str> {
+        self.modifiers
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+struct Token {
+    text: String,
+    /// The byte offset of the first `:` that appeared before any quote.
+    colon: Option<usize>,
+}
+
+impl Token {
+    fn modifier(&self) -> Option<(&str, &str)> {
+        let (key, value) = self.text.split_at(self.colon?);
+        let value = &value[1..];
+        let valid_key = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        // rules out urls such as `https://example.com`
+        (valid_key && !value.is_empty() && !value.starts_with('/')).then_some((key, value))
+    }
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut quote: Option<char> = None;
+    let mut seen_quote = false;
+    for c in input.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.get_or_insert_with(new_token).text.push(c),
+            None if c == '"' || c == '\'' => {
+                // apostrophes inside a word (eg `don't`) aren't quotes
+                if c == '\'' && current.as_ref().is_some_and(|t| !t.text.is_empty()) {
+                    current.get_or_insert_with(new_token).text.push(c);
+                } else {
+                    current.get_or_insert_with(new_token);
+                    quote = Some(c);
+                    seen_quote = true;
+                }
+            }
+            None if c.is_whitespace() => {
+                tokens.extend(current.take());
+                seen_quote = false;
+            }
+            None => {
+                let token = current.get_or_insert_with(new_token);
+                if c == ':' && token.colon.is_none() && !seen_quote {
+                    token.colon = Some(token.text.len());
+                }
+                token.text.push(c);
+            }
+        }
+    }
+    // an unterminated quote runs to the end of the query
+    tokens.extend(current);
+    tokens
+}
+
+fn new_token() -> Token {
+    Token {
+        text: String::new(),
+        colon: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("gh", "gh", &[], &[] ; "keyword only")]
+    #[test_case("gh rust-lang/rust #1", "gh", &["rust-lang/rust", "#1"], &[] ; "positional")]
+    #[test_case("gh  a \t b ", "gh", &["a", "b"], &[] ; "extra whitespace")]
+    #[test_case(r#"so "borrow checker" rust"#, "so", &["borrow checker", "rust"], &[] ; "double quoted")]
+    #[test_case("so 'borrow checker'", "so", &["borrow checker"], &[] ; "single quoted")]
+    #[test_case("w don't panic", "w", &["don't", "panic"], &[] ; "apostrophe")]
+    #[test_case(r#"so "unterminated quote"#, "so", &["unterminated quote"], &[] ; "unterminated")]
+    #[test_case(r#"so """#, "so", &[""], &[] ; "empty quotes")]
+    #[test_case("gh lang:rust async", "gh", &["async"], &[("lang", "rust")] ; "modifier")]
+    #[test_case(r#"gh lang:"rust lang""#, "gh", &[], &[("lang", "rust lang")] ; "quoted modifier")]
+    #[test_case(r#"gh "lang:rust""#, "gh", &["lang:rust"], &[] ; "quoted colon")]
+    #[test_case("w https://example.com", "w", &["https://example.com"], &[] ; "url")]
+    #[test_case("w :a b: c", "w", &[":a", "b:", "c"], &[] ; "empty key or value")]
+    fn parse(query: &str, keyword: &str, args: &[&str], modifiers: &[(&str, &str)]) {
+        let parsed = ParsedQuery::parse(query);
+        assert_eq!(keyword, parsed.keyword, "query: {:?}", query);
+        assert_eq!(args, parsed.args, "query: {:?}", query);
+        let modifiers: Vec<_> = modifiers
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert_eq!(modifiers, parsed.modifiers, "query: {:?}", query);
+    }
+
+    #[test]
+    fn raw_rest_is_untouched() {
+        let parsed = ParsedQuery::new("so", r#" "a  b" c:d "#);
+        assert_eq!(r#" "a  b" c:d "#, parsed.raw_rest);
+        assert_eq!(Some("a  b"), parsed.arg(0));
+        assert_eq!(Some("d"), parsed.modifier("c"));
+    }
+}
@@ -0,0 +1,499 @@
+use crate::bangs::{BangPack, Conflict};
+use crate::errors::{Error, Result};
+use crate::fallback::Fallback;
+use crate::handlers::Handler;
+use crate::pattern::Pattern;
+use crate::query::ParsedQuery;
+use crate::safeguards::Safeguards;
+use crate::shortcut::{Arguments, Resolution, Shortcut};
+use crate::template::Template;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use url::Url;
+
+const DEFAULT_CONFIG: &str = include_str!("../../resources/shortcuts.toml");
+
+/// A keyword that always redirects to the same url.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct Redirect {
+    keyword: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    description: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    category: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    examples: Vec<String>,
+    url: Url,
+}
+
+impl Shortcut for Redirect {
+    fn keywords(&self) -> Vec<&str> {
+        std::iter::once(&self.keyword)
+            .chain(&self.aliases)
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn category(&self) -> &str {
+        &self.category
+    }
+
+    fn examples(&self) -> Vec<&str> {
+        self.examples.iter().map(String::as_str).collect()
+    }
+
+    fn arguments(&self) -> Arguments {
+        Arguments::None
+    }
+
+    fn resolve(&self, _query: &ParsedQuery) -> Result<Resolution> {
+        Ok(self.url.clone().into())
+    }
+}
+
+/// A keyword followed by a query which is handed to either a built-in
+/// handler or a url template, or on its own goes to `url` if it's set.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    keyword: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    description: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    category: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    examples: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    separators: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    handler: Option<Handler>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    template: Option<Template>,
+    /// Where the keyword goes without a query, eg the home page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<Url>,
+}
+
+impl Shortcut for Entry {
+    fn keywords(&self) -> Vec<&str> {
+        std::iter::once(&self.keyword)
+            .chain(&self.aliases)
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn description(&self) -> &str {
+        match (&self.handler, self.description.is_empty()) {
+            (Some(handler), true) => handler.shortcut().description(),
+            _ => &self.description,
+        }
+    }
+
+    fn category(&self) -> &str {
+        match (&self.handler, self.category.is_empty()) {
+            (Some(handler), true) => handler.shortcut().category(),
+            _ => &self.category,
+        }
+    }
+
+    fn examples(&self) -> Vec<&str> {
+        match (&self.handler, self.examples.is_empty()) {
+            (Some(handler), true) => {
+                // the handler's examples only make sense under its own keyword
+                let shortcut = handler.shortcut();
+                let keywords = self.keywords();
+                if shortcut.keywords().iter().any(|k| keywords.contains(k)) {
+                    shortcut.examples()
+                } else {
+                    Vec::new()
+                }
+            }
+            _ => self.examples.iter().map(String::as_str).collect(),
+        }
+    }
+
+    fn separators(&self) -> Vec<&str> {
+        match (&self.handler, &self.separators) {
+            (_, Some(separators)) => separators.iter().map(String::as_str).collect(),
+            (Some(handler), None) => handler.shortcut().separators(),
+            (None, None) => vec![" "],
+        }
+    }
+
+    fn arguments(&self) -> Arguments {
+        match self.url {
+            Some(_) => Arguments::Optional,
+            None => Arguments::Required,
+        }
+    }
+
+    fn resolve(&self, query: &ParsedQuery) -> Result<Resolution> {
+        if let (Some(url), true) = (&self.url, query.raw_rest.is_empty()) {
+            return Ok(url.clone().into());
+        }
+        match (&self.handler, &self.template) {
+            (Some(handler), _) => handler.shortcut().resolve(query),
+            (None, Some(template)) => {
+                if let Some(arguments) = template.arguments() {
+                    if let Some(extra) = query.args.get(arguments) {
+                        let plural = if arguments == 1 { "" } else { "s" };
+                        return Err(Error::invalid_argument(
+                            extra,
+                            &format!(
+                                "{} takes at most {} argument{}",
+                                self.keyword, arguments, plural
+                            ),
+                        ));
+                    }
+                }
+                Ok(template.render(query)?.into())
+            }
+            (None, None) => Err(Error::InvalidConfig(format!(
+                "shortcut {:?} has neither a handler nor a template",
+                self.keyword
+            ))),
+        }
+    }
+}
+
+impl Entry {
+    fn validate(&self) -> Result<()> {
+        if self.keyword.is_empty() {
+            return Err(Error::InvalidConfig("shortcut keyword is empty".into()));
+        }
+        if self
+            .separators()
+            .iter()
+            .any(|separator| separator.is_empty())
+        {
+            return Err(Error::InvalidConfig(format!(
+                "shortcut {:?} has an empty separator",
+                self.keyword
+            )));
+        }
+        match (&self.handler, &self.template) {
+            (Some(_), Some(_)) => Err(Error::InvalidConfig(format!(
+                "shortcut {:?} has both a handler and a template",
+                self.keyword
+            ))),
+            (None, None) => Err(Error::InvalidConfig(format!(
+                "shortcut {:?} has neither a handler nor a template",
+                self.keyword
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The set of shortcuts used to turn a query into a url.
+///
+/// A registry is normally loaded from a TOML or JSON file (see
+/// `resources/shortcuts.toml` for the format) and `Registry::default()`
+/// provides the shortcuts that ship with this crate.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Registry {
+    /// Where ISBNs go, see
+    /// [`DEFAULT_BOOK_STORE`](crate::identifiers::DEFAULT_BOOK_STORE).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    book_store: Option<Template>,
+    /// The TLDs of internal hosts, see
+    /// [`DEFAULT_INTERNAL_TLDS`](crate::destination::DEFAULT_INTERNAL_TLDS).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    internal_tlds: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    safeguards: Option<Safeguards>,
+    #[serde(default, rename = "redirect", skip_serializing_if = "Vec::is_empty")]
+    redirects: Vec<Redirect>,
+    #[serde(default, rename = "shortcut", skip_serializing_if = "Vec::is_empty")]
+    shortcuts: Vec<Entry>,
+    #[serde(default, rename = "pattern", skip_serializing_if = "Vec::is_empty")]
+    patterns: Vec<Pattern>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fallback: Option<Fallback>,
+    #[serde(skip)]
+    bangs: BangPack,
+}
+
+impl Registry {
+    /// A registry without any shortcuts, unlike `Registry::default()`.
+    pub fn empty() -> Self {
+        Self {
+            book_store: None,
+            internal_tlds: None,
+            safeguards: None,
+            redirects: Vec::new(),
+            shortcuts: Vec::new(),
+            patterns: Vec::new(),
+            fallback: None,
+            bangs: BangPack::default(),
+        }
+    }
+
+    pub fn from_toml(config: &str) -> Result<Self> {
+        let registry: Self = toml::from_str(config)?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    pub fn from_json(config: &str) -> Result<Self> {
+        let registry: Self = serde_json::from_str(config)?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Loads a registry from a file, which is parsed as JSON if it has a
+    /// `.json` extension and as TOML otherwise.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let config = fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json(&config),
+            _ => Self::from_toml(&config),
+        }
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for redirect in &self.redirects {
+            if redirect.keyword.is_empty() {
+                return Err(Error::InvalidConfig("redirect keyword is empty".into()));
+            }
+        }
+        for shortcut in &self.shortcuts {
+            shortcut.validate()?;
+        }
+        for pattern in &self.patterns {
+            pattern.validate()?;
+        }
+        Ok(())
+    }
+
+    /// The search engine for queries that don't match a shortcut, if the
+    /// config sets one.
+    pub fn fallback(&self) -> Option<&Fallback> {
+        self.fallback.as_ref()
+    }
+
+    /// The url template ISBNs are sent to, if the config sets one.
+    pub fn book_store(&self) -> Option<&Template> {
+        self.book_store.as_ref()
+    }
+
+    /// The TLDs of internal hosts, if the config sets them.
+    pub fn internal_tlds(&self) -> Option<&[String]> {
+        self.internal_tlds.as_deref()
+    }
+
+    /// The limits on opening urls from the query, if the config sets them.
+    pub fn safeguards(&self) -> Option<&Safeguards> {
+        self.safeguards.as_ref()
+    }
+
+    /// Every keyword and alias in the registry.
+    pub fn keywords(&self) -> Vec<&str> {
+        self.redirects
+            .iter()
+            .flat_map(|redirect| redirect.keywords())
+            .chain(
+                self.shortcuts
+                    .iter()
+                    .flat_map(|shortcut| shortcut.keywords()),
+            )
+            .collect()
+    }
+
+    /// Adds a keyword that always goes to `url`.
+    pub fn add_redirect(&mut self, keyword: &str, description: &str, url: Url) {
+        self.redirects.push(Redirect {
+            keyword: keyword.to_string(),
+            aliases: Vec::new(),
+            description: description.to_string(),
+            category: String::new(),
+            examples: Vec::new(),
+            url,
+        });
+    }
+
+    /// Adds a keyword whose arguments are rendered into `template`.
+    pub fn add_template(&mut self, keyword: &str, description: &str, template: Template) {
+        self.shortcuts.push(Entry {
+            keyword: keyword.to_string(),
+            aliases: Vec::new(),
+            description: description.to_string(),
+            category: String::new(),
+            examples: Vec::new(),
+            separators: None,
+            handler: None,
+            template: Some(template),
+            url: None,
+        });
+    }
+
+    /// Adds a shortcut triggered by the query matching a regex.
+    pub fn add_pattern(&mut self, pattern: Pattern) {
+        self.patterns.push(pattern);
+    }
+
+    /// Adds DuckDuckGo bangs beneath the registry's shortcuts, returning the
+    /// bangs that are shadowed by a shortcut's keyword.
+    pub fn add_bangs(&mut self, bangs: BangPack) -> Vec<Conflict> {
+        let conflicts = bangs.conflicts(self.keywords());
+        self.bangs.extend(bangs);
+        conflicts
+    }
+
+    pub(crate) fn take_bangs(&mut self) -> BangPack {
+        std::mem::take(&mut self.bangs)
+    }
+
+    pub(crate) fn take_patterns(&mut self) -> Vec<Pattern> {
+        std::mem::take(&mut self.patterns)
+    }
+
+    /// Converts the registry into shortcuts that can be added to a
+    /// [`Resolver`](crate::Resolver).
+    pub fn into_shortcuts(self) -> Vec<Box<dyn Shortcut>> {
+        let redirects = self
+            .redirects
+            .into_iter()
+            .map(|redirect| Box::new(redirect) as Box<dyn Shortcut>);
+        let shortcuts = self
+            .shortcuts
+            .into_iter()
+            .map(|shortcut| Box::new(shortcut) as Box<dyn Shortcut>);
+        redirects.chain(shortcuts).collect()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::from_toml(DEFAULT_CONFIG).expect("the default shortcuts config is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Resolver;
+    use test_case::test_case;
+
+    const TEAM_TOML: &str = r#"
+        book_store = "https://books.example.com/{rest|path}"
+        internal_tlds = ["corp"]
+
+        [[redirect]]
+        keyword = "wiki"
+        aliases = ["kb"]
+        url = "https://wiki.example.com/"
+
+        [[shortcut]]
+        keyword = "jira"
+        template = "https://jira.example.com/browse/?q={rest}"
+        url = "https://jira.example.com/"
+
+        [[shortcut]]
+        keyword = "gh"
+        handler = "github"
+
+        [safeguards]
+        deny = ["evil.net"]
+
+        [fallback]
+        engine = "kagi"
+    "#;
+
+    const TEAM_JSON: &str = r#"{
+        "book_store": "https://books.example.com/{rest|path}",
+        "internal_tlds": ["corp"],
+        "redirect": [{"keyword": "wiki", "aliases": ["kb"], "url": "https://wiki.example.com/"}],
+        "shortcut": [
+            {"keyword": "jira", "template": "https://jira.example.com/browse/?q={rest}", "url": "https://jira.example.com/"},
+            {"keyword": "gh", "handler": "github"}
+        ],
+        "safeguards": {"deny": ["evil.net"]},
+        "fallback": {"engine": "kagi"}
+    }"#;
+
+    #[test_case("https://wiki.example.com/", "wiki")]
+    #[test_case("https://wiki.example.com/", "KB ")]
+    #[test_case(
+        "https://jira.example.com/browse/?q=ABC-123+lol%2Fdonkey",
+        "jira ABC-123 lol/donkey"
+    )]
+    #[test_case("https://jira.example.com/", "Jira" ; "without optional arguments")]
+    #[test_case("https://github.com/rust-lang/rust", "gh rust-lang/rust")]
+    #[test_case("https://kagi.com/search?q=w+test", "w test")]
+    #[test_case("https://books.example.com/9780306406157", "0-306-40615-2" ; "book store")]
+    #[test_case("http://wiki.corp/", "wiki.corp" ; "internal tld")]
+    #[test_case("https://kagi.com/search?q=router.lan", "router.lan" ; "replaces the default internal tlds")]
+    #[test_case("https://kagi.com/search?q=www.evil.net%2Flogin", "www.evil.net/login" ; "denied domain")]
+    fn custom_registry(expected: &str, query: &str) -> Result<()> {
+        for registry in [
+            Registry::from_toml(TEAM_TOML)?,
+            Registry::from_json(TEAM_JSON)?,
+        ] {
+            let resolver = Resolver::builder().registry(registry).build();
+            let actual = resolver.resolve(query)?.url;
+            assert_eq!(expected, actual.as_str(), "query: {:?}", query);
+        }
+        Ok(())
+    }
+
+    #[test_case(r#"[[shortcut]]
+        keyword = "a""# ; "no handler or template")]
+    #[test_case(r#"[[shortcut]]
+        keyword = "a"
+        handler = "github"
+        template = "https://example.com/?q={rest}""# ; "handler and template")]
+    #[test_case(r#"[[shortcut]]
+        keyword = "a"
+        handler = "gitlab""# ; "unknown handler")]
+    #[test_case(r#"[[shortcut]]
+        keyword = "a"
+        template = "example.com/?q={rest}""# ; "relative template")]
+    #[test_case(r#"[[shortcut]]
+        keyword = "a"
+        template = "https://example.com/?q={rest|base64}""# ; "unknown template filter")]
+    #[test_case(r#"[[redirect]]
+        keyword = "a"
+        url = "not a url""# ; "invalid redirect url")]
+    #[test_case(r#"book_store = "books.example.com/{rest}""# ; "relative book store")]
+    #[test_case(r#"[[pattern]]
+        name = "a"
+        pattern = "a("
+        template = "https://example.com/""# ; "invalid pattern")]
+    fn invalid_config(config: &str) {
+        assert!(Registry::from_toml(config).is_err());
+    }
+
+    #[test]
+    fn toml_round_trip() -> Result<()> {
+        let registry = Registry::from_toml(TEAM_TOML)?;
+        let round_tripped = Registry::from_toml(&registry.to_toml()?)?;
+        assert_eq!(registry.to_toml()?, round_tripped.to_toml()?);
+        assert_eq!(vec!["wiki", "kb", "jira", "gh"], round_tripped.keywords());
+        Ok(())
+    }
+
+    #[test]
+    fn default_round_trip() -> Result<()> {
+        let registry = Registry::default();
+        Registry::from_toml(&registry.to_toml()?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,960 @@
+use crate::bangs::BangPack;
+use crate::destination;
+use crate::errors::{Error, Result};
+use crate::explain::{Explanation, Rule, Step, Trace};
+use crate::fallback::Fallback;
+use crate::fuzzy;
+use crate::identifiers::{Identifier, DEFAULT_BOOK_STORE};
+use crate::normalize::{normalize, same_keyword, strip_prefix_ignore_case};
+use crate::pattern::{Pattern, Priority};
+use crate::query::ParsedQuery;
+use crate::registry::Registry;
+use crate::safeguards::Safeguards;
+use crate::shortcut::{Arguments, Resolution, Shortcut};
+use crate::suggest::{Suggestion, Suggestions, MAX_SUGGESTIONS};
+use crate::template::Template;
+
+use std::collections::HashSet;
+
+/// The most corrected queries offered for a typo.
+const MAX_DID_YOU_MEAN: usize = 3;
+
+/// Turns queries into urls using a list of shortcuts.
+///
+/// Shortcuts are tried in the order they were added, so anything added
+/// before [`ResolverBuilder::registry`] takes precedence over the registry.
+pub struct Resolver {
+    shortcuts: Vec<Box<dyn Shortcut>>,
+    patterns: Vec<Pattern>,
+    bangs: BangPack,
+    book_store: Template,
+    internal_tlds: Vec<String>,
+    safeguards: Safeguards,
+    fallback: Fallback,
+}
+
+#[derive(Default)]
+pub struct ResolverBuilder {
+    shortcuts: Vec<Box<dyn Shortcut>>,
+    patterns: Vec<Pattern>,
+    bangs: BangPack,
+    book_store: Option<Template>,
+    internal_tlds: Option<Vec<String>>,
+    safeguards: Option<Safeguards>,
+    fallback: Option<Fallback>,
+}
+
+impl ResolverBuilder {
+    pub fn shortcut(mut self, shortcut: Box<dyn Shortcut>) -> Self {
+        self.shortcuts.push(shortcut);
+        self
+    }
+
+    pub fn shortcuts(mut self, shortcuts: impl IntoIterator<Item = Box<dyn Shortcut>>) -> Self {
+        self.shortcuts.extend(shortcuts);
+        self
+    }
+
+    /// Adds a shortcut triggered by the query matching a regex. Patterns
+    /// with the same priority are tried in the order they were added.
+    pub fn pattern(mut self, pattern: Pattern) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Sets the search engine for queries that don't match a shortcut,
+    /// which defaults to DuckDuckGo.
+    pub fn fallback(mut self, fallback: Fallback) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Sets where pasted ISBNs go, which defaults to a Booktopia search. The
+    /// ISBN-13 is the template's `{rest}`.
+    pub fn book_store(mut self, book_store: Template) -> Self {
+        self.book_store = Some(book_store);
+        self
+    }
+
+    /// Sets the TLDs of internal hosts, which are opened over http when typed
+    /// as a domain, eg `router.lan`. Defaults to
+    /// [`DEFAULT_INTERNAL_TLDS`](destination::DEFAULT_INTERNAL_TLDS).
+    pub fn internal_tlds(mut self, tlds: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.internal_tlds = Some(tlds.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Limits which urls, domains and addresses in the query are opened.
+    pub fn safeguards(mut self, safeguards: Safeguards) -> Self {
+        self.safeguards = Some(safeguards);
+        self
+    }
+
+    /// Adds DuckDuckGo bangs, which are only used for `!bang`s that don't
+    /// match any shortcut.
+    pub fn bangs(mut self, bangs: BangPack) -> Self {
+        self.bangs.extend(bangs);
+        self
+    }
+
+    /// Adds the registry's shortcuts, patterns and bangs, and its fallback,
+    /// book store and internal TLDs if they haven't been set already.
+    pub fn registry(mut self, mut registry: Registry) -> Self {
+        if self.fallback.is_none() {
+            self.fallback = registry.fallback().cloned();
+        }
+        if self.book_store.is_none() {
+            self.book_store = registry.book_store().cloned();
+        }
+        if self.internal_tlds.is_none() {
+            self.internal_tlds = registry.internal_tlds().map(<[String]>::to_vec);
+        }
+        if self.safeguards.is_none() {
+            self.safeguards = registry.safeguards().cloned();
+        }
+        self.bangs.extend(registry.take_bangs());
+        self.patterns.extend(registry.take_patterns());
+        self.shortcuts(registry.into_shortcuts())
+    }
+
+    pub fn build(self) -> Resolver {
+        Resolver {
+            shortcuts: self.shortcuts,
+            patterns: self.patterns,
+            bangs: self.bangs,
+            book_store: self.book_store.unwrap_or_else(|| {
+                Template::parse(DEFAULT_BOOK_STORE).expect("the default book store is valid")
+            }),
+            internal_tlds: self.internal_tlds.unwrap_or_else(|| {
+                destination::DEFAULT_INTERNAL_TLDS
+                    .iter()
+                    .map(|tld| tld.to_string())
+                    .collect()
+            }),
+            safeguards: self.safeguards.unwrap_or_default(),
+            fallback: self.fallback.unwrap_or_default(),
+        }
+    }
+}
+
+impl Resolver {
+    pub fn builder() -> ResolverBuilder {
+        ResolverBuilder::default()
+    }
+
+    /// The search engine for queries that don't match a shortcut.
+    pub fn fallback(&self) -> &Fallback {
+        &self.fallback
+    }
+
+    /// The limits on opening urls, domains and addresses in the query.
+    pub fn safeguards(&self) -> &Safeguards {
+        &self.safeguards
+    }
+
+    pub fn shortcuts(&self) -> impl Iterator<Item = &dyn Shortcut> {
+        self.shortcuts.iter().map(|shortcut| shortcut.as_ref())
+    }
+
+    pub fn patterns(&self) -> impl Iterator<Item = &Pattern> {
+        self.patterns.iter()
+    }
+
+    fn handle_pattern(
+        &self,
+        priority: Priority,
+        query: &str,
+        trace: &mut Trace,
+    ) -> Result<Option<Resolution>> {
+        let mut patterns = self
+            .patterns()
+            .filter(|pattern| pattern.priority() == priority)
+            .peekable();
+        if patterns.peek().is_none() {
+            return Ok(None);
+        }
+        for pattern in patterns {
+            if let Some(captures) = pattern.captures(query) {
+                trace.record(|| {
+                    Step::new(Rule::Pattern, true, pattern.description())
+                        .keyword(pattern.name())
+                        .arguments(&captures.args)
+                });
+                let resolution = Resolution::from(pattern.render(&captures)?);
+                return Ok(Some(resolution.matched(
+                    Rule::Pattern,
+                    Some(pattern.name()),
+                    &captures.args,
+                )));
+            }
+        }
+        trace.record(|| Step::new(Rule::Pattern, false, "no pattern matches the query"));
+        Ok(None)
+    }
+
+    fn handle_destination(&self, query: &str, trace: &mut Trace) -> Result<Option<Resolution>> {
+        if !self.safeguards.open_destinations {
+            trace.record(|| {
+                Step::new(
+                    Rule::Domain,
+                    false,
+                    "opening urls, domains and IP addresses is disabled",
+                )
+            });
+            return Ok(None);
+        }
+        match destination::detect(query, &self.internal_tlds)? {
+            Some(destination) => {
+                if let Some(reason) = self.safeguards.blocked(&destination.url) {
+                    trace.record(|| Step::new(Rule::Domain, false, reason));
+                    return Ok(None);
+                }
+                trace.record(|| Step::new(Rule::Domain, true, destination.kind.describe()));
+                let resolution = Resolution::from(destination.url);
+                Ok(Some(resolution.matched(Rule::Domain, None, &[])))
+            }
+            None => {
+                trace.record(|| {
+                    Step::new(
+                        Rule::Domain,
+                        false,
+                        "the query isn't a url, domain or IP address",
+                    )
+                });
+                Ok(None)
+            }
+        }
+    }
+
+    fn handle_identifier(&self, query: &str, trace: &mut Trace) -> Result<Option<Resolution>> {
+        let identifier = match Identifier::detect(query)? {
+            Some(identifier) => identifier,
+            None => {
+                trace.record(|| {
+                    Step::new(
+                        Rule::Identifier,
+                        false,
+                        "the query isn't a DOI, arXiv id, PubMed id or ISBN",
+                    )
+                });
+                return Ok(None);
+            }
+        };
+        let arguments = [identifier.to_string()];
+        trace.record(|| {
+            Step::new(Rule::Identifier, true, identifier.name())
+                .keyword(identifier.name())
+                .arguments(&arguments)
+        });
+        let resolution = Resolution::from(identifier.url(&self.book_store)?);
+        Ok(Some(resolution.matched(
+            Rule::Identifier,
+            Some(identifier.name()),
+            &arguments,
+        )))
+    }
+
+    fn handle_bare_keyword(&self, query: &str, trace: &mut Trace) -> Result<Option<Resolution>> {
+        // this is to handle autocomplete on mobile; ie matching "weather"
+        // when the input is "Weather "
+        let query = query.trim();
+        for shortcut in self.shortcuts() {
+            if shortcut.arguments() == Arguments::Required {
+                continue;
+            }
+            if let Some(keyword) = shortcut
+                .keywords()
+                .into_iter()
+                .find(|keyword| same_keyword(keyword, query))
+            {
+                trace.record(|| {
+                    Step::new(Rule::BareKeyword, true, shortcut.description()).keyword(keyword)
+                });
+                return shortcut
+                    .resolve(&ParsedQuery::new(keyword, ""))
+                    .map(|r| Some(r.matched(Rule::BareKeyword, Some(keyword), &[])));
+            }
+        }
+        trace.record(|| {
+            Step::new(
+                Rule::BareKeyword,
+                false,
+                "no keyword without arguments matches the whole query",
+            )
+        });
+        Ok(None)
+    }
+
+    fn handle_keyword(&self, query: &str, trace: &mut Trace) -> Result<Option<Resolution>> {
+        for shortcut in self.shortcuts() {
+            if shortcut.arguments() == Arguments::None {
+                continue;
+            }
+            if let Some(query) = strip_keyword(shortcut, query) {
+                trace.record(|| {
+                    Step::new(Rule::Keyword, true, shortcut.description())
+                        .keyword(&query.keyword)
+                        .arguments(&query.args)
+                });
+                return shortcut
+                    .resolve(&query)
+                    .map(|r| Some(r.matched(Rule::Keyword, Some(&query.keyword), &query.args)));
+            }
+        }
+        trace.record(|| {
+            Step::new(
+                Rule::Keyword,
+                false,
+                "the query doesn't start with a keyword and separator",
+            )
+        });
+        Ok(None)
+    }
+
+    /// Handles DuckDuckGo style `!keyword` bangs at the start, middle or end
+    /// of the query, eg `rust async !gh`. Shortcuts are preferred over any
+    /// imported bangs and unknown bangs are left for the fallback engine,
+    /// which may well understand them or else searches for them as typed.
+    fn handle_bang(&self, query: &str, trace: &mut Trace) -> Result<Option<Resolution>> {
+        let mut found = false;
+        for (start, token) in tokens(query) {
+            let keyword = match token.strip_prefix('!') {
+                Some(keyword) if !keyword.is_empty() => keyword,
+                _ => continue,
+            };
+            found = true;
+            let rest = format!(
+                "{} {}",
+                query[..start].trim_end(),
+                query[start + token.len()..].trim_start()
+            );
+            let rest = rest.trim();
+            if rest.is_empty() {
+                if let Some(resolution) =
+                    self.handle_bare_keyword(keyword, &mut Trace::default())?
+                {
+                    trace.record(|| Step::new(Rule::Bang, true, "bare keyword").keyword(keyword));
+                    return Ok(Some(resolution.matched(Rule::Bang, Some(keyword), &[])));
+                }
+            }
+            for shortcut in self.shortcuts() {
+                if shortcut.arguments() == Arguments::None {
+                    continue;
+                }
+                if let Some(keyword) = shortcut
+                    .keywords()
+                    .into_iter()
+                    .find(|k| same_keyword(k, keyword))
+                {
+                    if rest.is_empty() && shortcut.arguments() == Arguments::Required {
+                        // the same as the keyword on its own, eg `!w`
+                        trace.record(|| {
+                            Step::new(Rule::Bang, false, "the shortcut needs arguments")
+                                .keyword(keyword)
+                        });
+                        break;
+                    }
+                    let query = ParsedQuery::new(keyword, rest);
+                    trace.record(|| {
+                        Step::new(Rule::Bang, true, shortcut.description())
+                            .keyword(keyword)
+                            .arguments(&query.args)
+                    });
+                    return shortcut
+                        .resolve(&query)
+                        .map(|r| Some(r.matched(Rule::Bang, Some(keyword), &query.args)));
+                }
+            }
+            if let Some(url) = self.bangs.resolve(keyword, rest) {
+                trace.record(|| Step::new(Rule::Bang, true, "imported bang").keyword(keyword));
+                let resolution = Resolution::from(url?);
+                return Ok(Some(resolution.matched(Rule::Bang, Some(keyword), &[])));
+            }
+            // searching for the query as typed is better than an error even
+            // if the engine doesn't know bangs, eg `css !important`
+            let detail = if self.fallback.supports_bangs() {
+                "unknown bang, left for the fallback engine"
+            } else {
+                "unknown bang, searched for as typed"
+            };
+            trace.record(|| Step::new(Rule::Bang, false, detail).keyword(keyword));
+        }
+        if !found {
+            trace.record(|| Step::new(Rule::Bang, false, "the query has no !bang"));
+        }
+        Ok(None)
+    }
+
+    /// Corrects a mistyped keyword if there's only one it could be and the
+    /// typo can't be a real word. Otherwise asks which was meant with
+    /// [`Error::DidYouMean`] if the rest of the query suits the shortcut, or
+    /// searches with the corrections in [`Resolution::did_you_mean`].
+    fn handle_typo(&self, query: &str, trace: &mut Trace) -> Result<Option<Resolution>> {
+        let query = query.trim();
+        let (typed, rest) = match query.split_once(char::is_whitespace) {
+            Some((typed, rest)) => (typed, Some(rest.trim_start())),
+            None => (query, None),
+        };
+        let typed = typed.to_lowercase();
+        let mut slips = Vec::new();
+        let mut near_misses = Vec::new();
+        // the corrected queries whose rest suits the shortcut, which are
+        // asked about rather than searched for
+        let mut fitting = Vec::new();
+        for shortcut in self.shortcuts() {
+            // a keyword on its own can't be one that needs a query and
+            // vice versa
+            let skip = match shortcut.arguments() {
+                Arguments::None => rest.is_some(),
+                Arguments::Required => rest.is_none(),
+                Arguments::Optional => false,
+            };
+            if skip {
+                continue;
+            }
+            for keyword in shortcut.keywords() {
+                let keyword = keyword.to_lowercase();
+                if slips.contains(&keyword) || near_misses.contains(&keyword) {
+                    continue;
+                }
+                if fuzzy::is_slip(&typed, &keyword) {
+                    slips.push(keyword.clone());
+                } else if fuzzy::is_near_miss(&typed, &keyword) {
+                    near_misses.push(keyword.clone());
+                } else {
+                    continue;
+                }
+                if rest.is_some_and(|rest| fits_arguments(shortcut, &keyword, rest)) {
+                    fitting.push(keyword);
+                }
+            }
+        }
+        let corrected = |keyword: &String| match rest {
+            Some(rest) => format!("{} {}", keyword, rest),
+            None => keyword.clone(),
+        };
+
+        // a correction that could be wrong is only ever suggested, so that
+        // searches like `cord cutting` aren't sent to a shortcut
+        match slips.as_slice() {
+            [keyword] if fuzzy::is_autocorrectable(&typed, keyword) => {
+                trace.record(|| {
+                    Step::new(Rule::Typo, true, format!("corrected `{}`", typed)).keyword(keyword)
+                });
+                let corrected = corrected(keyword);
+                return match rest {
+                    Some(_) => self.handle_keyword(&corrected, trace),
+                    None => self.handle_bare_keyword(&corrected, trace),
+                };
+            }
+            _ => {}
+        }
+        let mut suggestions: Vec<_> = slips.iter().chain(&near_misses).map(corrected).collect();
+        suggestions.truncate(MAX_DID_YOU_MEAN);
+        if suggestions.is_empty() {
+            trace.record(|| Step::new(Rule::Typo, false, "no keyword is close to the query"));
+            return Ok(None);
+        }
+        if fitting.is_empty() {
+            // most likely a search that happens to start with a word close
+            // to a keyword, eg `crate and barrel`
+            trace.record(|| {
+                let detail = format!(
+                    "the query reads like a search, so `{}` is only offered",
+                    suggestions.join("` or `")
+                );
+                Step::new(Rule::Typo, false, detail)
+            });
+            return self.handle_fallback(query, suggestions, trace).map(Some);
+        }
+        trace.record(|| {
+            Step::new(
+                Rule::Typo,
+                false,
+                "the query is close to more than one keyword, or not close enough to correct",
+            )
+        });
+        Err(Error::DidYouMean(suggestions))
+    }
+
+    /// Searches for the query with the fallback engine, offering any
+    /// corrected queries alongside.
+    fn handle_fallback(
+        &self,
+        query: &str,
+        did_you_mean: Vec<String>,
+        trace: &mut Trace,
+    ) -> Result<Resolution> {
+        trace.record(|| Step::new(Rule::Fallback, true, "the fallback search engine"));
+        let mut resolution =
+            Resolution::from(self.fallback.url(query)?).matched(Rule::Fallback, None, &[]);
+        resolution.did_you_mean = did_you_mean;
+        Ok(resolution)
+    }
+
+    pub fn resolve(&self, query: &str) -> Result<Resolution> {
+        self.resolve_traced(query, &mut Trace::default())
+    }
+
+    /// Resolves a query, recording every rule that was tried.
+    pub fn explain(&self, query: &str) -> Explanation {
+        let mut trace = Trace::enabled();
+        let result = self.resolve_traced(query, &mut trace);
+        let (url, error) = match result {
+            Ok(resolution) => (Some(resolution.url), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        Explanation {
+            query: query.to_string(),
+            steps: trace.into_steps(),
+            url,
+            error,
+        }
+    }
+
+    fn resolve_traced(&self, query: &str, trace: &mut Trace) -> Result<Resolution> {
+        let query = &normalize(query);
+        if let Some(resolution) = self.handle_pattern(Priority::First, query, trace)? {
+            return Ok(resolution);
+        }
+        if let Some(resolution) = self.handle_bare_keyword(query, trace)? {
+            return Ok(resolution);
+        }
+        if let Some(resolution) = self.handle_keyword(query, trace)? {
+            return Ok(resolution);
+        }
+        if let Some(resolution) = self.handle_bang(query, trace)? {
+            return Ok(resolution);
+        }
+        if let Some(resolution) = self.handle_pattern(Priority::BeforeDomain, query, trace)? {
+            return Ok(resolution);
+        }
+        // before destinations as old style arXiv ids with a subject class
+        // look like a domain, eg `math.GT/0309136`
+        if let Some(resolution) = self.handle_identifier(query, trace)? {
+            return Ok(resolution);
+        }
+        if let Some(resolution) = self.handle_destination(query, trace)? {
+            return Ok(resolution);
+        }
+        if let Some(resolution) = self.handle_pattern(Priority::Last, query, trace)? {
+            return Ok(resolution);
+        }
+        if let Some(resolution) = self.handle_typo(query, trace)? {
+            return Ok(resolution);
+        }
+        self.handle_fallback(query, Vec::new(), trace)
+    }
+
+    /// Suggests completions for a partly typed query: matching keywords while
+    /// the keyword is being typed, then where the query will go.
+    pub fn suggest(&self, query: &str) -> Suggestions {
+        let query = normalize(query);
+        let query = query.trim_start();
+        let suggestions = if query.contains(char::is_whitespace) {
+            self.suggest_resolution(query)
+        } else {
+            self.suggest_keywords(query)
+        };
+        Suggestions {
+            query: query.to_string(),
+            suggestions,
+        }
+    }
+
+    fn suggest_keywords(&self, prefix: &str) -> Vec<Suggestion> {
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+        let prefix = prefix.to_lowercase();
+        let mut seen = HashSet::new();
+        let mut suggestions = Vec::new();
+        for shortcut in self.shortcuts() {
+            for keyword in shortcut.keywords() {
+                let keyword = keyword.to_lowercase();
+                // the first shortcut with a keyword is the one it resolves to
+                if !keyword.starts_with(&prefix) || !seen.insert(keyword.clone()) {
+                    continue;
+                }
+                let url = match shortcut.arguments() {
+                    Arguments::None | Arguments::Optional => {
+                        self.resolve(&keyword).ok().map(|r| r.url)
+                    }
+                    Arguments::Required => None,
+                };
+                suggestions.push(Suggestion {
+                    completion: keyword,
+                    description: shortcut.description().to_string(),
+                    url,
+                });
+            }
+        }
+        // exact matches first, then the shortest keywords
+        suggestions.sort_by_key(|s| (s.completion != prefix, s.completion.len()));
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+
+    fn suggest_resolution(&self, query: &str) -> Vec<Suggestion> {
+        let shortcut = self.shortcuts().find(|shortcut| {
+            shortcut.arguments() != Arguments::None && strip_keyword(*shortcut, query).is_some()
+        });
+        match (shortcut, self.resolve(query)) {
+            (Some(shortcut), Ok(resolution)) => vec![Suggestion {
+                completion: query.to_string(),
+                description: shortcut.description().to_string(),
+                url: Some(resolution.url),
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::builder().registry(Registry::default()).build()
+    }
+}
+
+/// Splits the query into the shortcut's keyword and the rest of the query if
+/// it starts with one of the shortcut's keywords and separators.
+fn strip_keyword(shortcut: &dyn Shortcut, query: &str) -> Option<ParsedQuery> {
+    let separators = shortcut.separators();
+    shortcut.keywords().into_iter().find_map(|keyword| {
+        let rest = strip_prefix_ignore_case(query, keyword)?;
+        separators.iter().find_map(|separator| {
+            rest.strip_prefix(separator)
+                .map(|rest| ParsedQuery::new(keyword, rest))
+        })
+    })
+}
+
+/// Whether the rest of a mistyped query is meant for the shortcut rather than
+/// more of a search: the shortcut accepts it and it isn't only words, eg
+/// `520` in `twit 520` but not `cutting` in `cord cutting`.
+fn fits_arguments(shortcut: &dyn Shortcut, keyword: &str, rest: &str) -> bool {
+    let words = rest
+        .split_whitespace()
+        .all(|word| word.chars().all(char::is_alphabetic));
+    !words && shortcut.resolve(&ParsedQuery::new(keyword, rest)).is_ok()
+}
+
+/// The whitespace separated tokens in the query and their byte offsets.
+fn tokens(query: &str) -> impl Iterator<Item = (usize, &str)> {
+    query
+        .split(char::is_whitespace)
+        .filter(|token| !token.is_empty())
+        .map(move |token| (token.as_ptr() as usize - query.as_ptr() as usize, token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::Github;
+    use test_case::test_case;
+    use url::Url;
+
+    struct Jira;
+
+    impl Shortcut for Jira {
+        fn keywords(&self) -> Vec<&str> {
+            vec!["jira", "j"]
+        }
+
+        fn description(&self) -> &str {
+            "Jira issues"
+        }
+
+        fn resolve(&self, query: &ParsedQuery) -> Result<Resolution> {
+            let url = Url::parse("https://jira.example.com/browse/")?;
+            Ok(url.join(&query.raw_rest.to_uppercase())?.into())
+        }
+    }
+
+    #[test_case("https://jira.example.com/browse/ABC-123", "jira abc-123")]
+    #[test_case("https://jira.example.com/browse/ABC-123", "j abc-123")]
+    #[test_case("https://github.com/rust-lang/rust", "gh rust-lang/rust")]
+    #[test_case("https://www.twitch.tv/", "t")]
+    #[test_case("https://duckduckgo.com/?k1=-1&q=jira", "jira")]
+    #[test_case("https://jira.example.com/browse/ABC-123", "abc-123\u{a0}!j" ; "bang after nbsp")]
+    #[test_case("https://www.youtube.com/results?search_query=cats", "cats !yt" ; "imported bang")]
+    #[test_case("https://github.com/search?q=cats", "!gh cats" ; "shortcut beats bang")]
+    fn custom_shortcuts(expected: &str, query: &str) -> Result<()> {
+        let resolver = Resolver::builder()
+            .shortcut(Box::new(Jira))
+            .shortcut(Box::new(Github))
+            .registry(Registry::from_toml(
+                r#"
+                [[redirect]]
+                keyword = "t"
+                url = "https://www.twitch.tv/"
+                "#,
+            )?)
+            .bangs(BangPack::from_json(
+                r#"[
+                {"s": "GitHub", "t": "gh", "u": "https://github.com/search?q={{{s}}}&type=code"},
+                {"s": "YouTube", "t": "yt", "u": "https://www.youtube.com/results?search_query={{{s}}}"}
+                ]"#,
+            )?)
+            .build();
+        let actual = resolver.resolve(query)?.url;
+        assert_eq!(expected, actual.as_str(), "query: {:?}", query);
+        Ok(())
+    }
+
+    #[test_case("d", &["dh", "docs"])]
+    #[test_case("DO", &["docs"] ; "case insensitive")]
+    #[test_case("gh", &["gh"] ; "optional arguments")]
+    #[test_case("/", &["/."] ; "alias")]
+    #[test_case("zzz", &[])]
+    #[test_case("", &[])]
+    fn suggest_keywords(query: &str, expected: &[&str]) {
+        let suggestions = Resolver::default().suggest(query);
+        let completions: Vec<_> = suggestions
+            .suggestions
+            .iter()
+            .map(|s| s.completion.as_str())
+            .collect();
+        assert_eq!(expected, completions);
+    }
+
+    #[test]
+    fn suggest_serialization() -> Result<()> {
+        let resolver = Resolver::default();
+        assert_eq!(
+            r#"["twi",["twir"],["This Week in Rust, or an issue by number."],["https://this-week-in-rust.org/"]]"#,
+            serde_json::to_string(&resolver.suggest("twi"))?
+        );
+        assert_eq!(
+            r#"["dh rust",["dh rust"],["Docker Hub official images (`/nginx`), repos or search."],["https://hub.docker.com/search?q=rust"]]"#,
+            serde_json::to_string(&resolver.suggest("dh rust"))?
+        );
+        assert_eq!(
+            r#"["no shortcut here",[],[],[]]"#,
+            serde_json::to_string(&resolver.suggest("no shortcut here"))?
+        );
+        Ok(())
+    }
+
+    #[test_case(
+        "www.example. com",
+        &[(Rule::BareKeyword, false), (Rule::Keyword, false), (Rule::Bang, false), (Rule::Identifier, false), (Rule::Domain, true)],
+        "https://www.example.com/"
+    )]
+    #[test_case(
+        "www.example.com",
+        &[(Rule::BareKeyword, false), (Rule::Keyword, false), (Rule::Bang, false), (Rule::Identifier, false), (Rule::Domain, true)],
+        "https://www.example.com/"
+    )]
+    #[test_case(
+        "lol donkey",
+        &[(Rule::BareKeyword, false), (Rule::Keyword, false), (Rule::Bang, false), (Rule::Identifier, false), (Rule::Domain, false), (Rule::Pattern, false), (Rule::Typo, false), (Rule::Fallback, true)],
+        "https://duckduckgo.com/?k1=-1&q=lol+donkey"
+    )]
+    #[test_case("twir", &[(Rule::BareKeyword, true)], "https://this-week-in-rust.org/")]
+    #[test_case(
+        "rust !nope !so",
+        &[(Rule::BareKeyword, false), (Rule::Keyword, false), (Rule::Bang, false), (Rule::Bang, true)],
+        "https://stackoverflow.com/search?q=rust+%21nope"
+    )]
+    fn explain_steps(query: &str, expected: &[(Rule, bool)], url: &str) {
+        let explanation = Resolver::default().explain(query);
+        let steps: Vec<_> = explanation
+            .steps
+            .iter()
+            .map(|step| (step.rule, step.matched))
+            .collect();
+        assert_eq!(expected, steps);
+        assert_eq!(Some(url), explanation.url.as_ref().map(Url::as_str));
+    }
+
+    #[test]
+    fn explain_arguments() -> Result<()> {
+        let explanation = Resolver::default().explain("gh rust-lang/rust #1");
+        let step = explanation.steps.last().unwrap();
+        assert_eq!(Some("gh"), step.keyword.as_deref());
+        assert_eq!(vec!["rust-lang/rust", "#1"], step.arguments);
+        let json = serde_json::to_value(&explanation)?;
+        assert_eq!("keyword", json["steps"][1]["rule"]);
+        assert_eq!("https://github.com/rust-lang/rust/issues/1", json["url"]);
+        assert!(explanation.to_html().contains("<code>#1</code>"));
+        Ok(())
+    }
+
+    #[test]
+    fn explain_typo_search() {
+        let explanation = Resolver::default().explain("crate and barrel");
+        let typo = explanation
+            .steps
+            .iter()
+            .find(|step| step.rule == Rule::Typo)
+            .unwrap();
+        assert_eq!(
+            "the query reads like a search, so `crates and barrel` is only offered",
+            typo.detail
+        );
+    }
+
+    #[test]
+    fn explain_error() -> Result<()> {
+        let resolver = Resolver::builder()
+            .registry(Registry::from_toml(
+                r#"
+                [[shortcut]]
+                keyword = "two"
+                template = "https://example.com/{1}/{2}"
+                "#,
+            )?)
+            .build();
+        let explanation = resolver.explain("two one");
+        assert_eq!(None, explanation.url);
+        assert_eq!(Some("Missing argument: 2"), explanation.error.as_deref());
+        Ok(())
+    }
+
+    #[test_case(
+        Resolver::default(),
+        "gh rust-lang/rust !abc",
+        "Invalid argument \"!abc\": issues and pull requests are numbers, eg `#1` or `!1`"
+    )]
+    #[test_case(
+        Resolver::default(),
+        "docs se<rde",
+        "Invalid argument \"se<rde\": crate names only contain letters, numbers, `-` and `_`"
+    )]
+    #[test_case(Resolver::default(), "a%zz .com", "Invalid domain: a%zz.com")]
+    #[test_case(
+        Resolver::default(),
+        "x 927 foo",
+        "Invalid argument \"foo\": x takes at most 1 argument" ; "extra argument"
+    )]
+    fn user_errors(resolver: Resolver, query: &str, expected: &str) {
+        let err = resolver.resolve(query).unwrap_err();
+        assert_eq!(expected, err.to_string());
+        assert_eq!(400, err.status());
+    }
+
+    #[test_case("css !important", "https://www.google.com/search?q=css+%21important")]
+    #[test_case("rust !Send", "https://www.google.com/search?q=rust+%21Send")]
+    #[test_case("!nope cats", "https://www.google.com/search?q=%21nope+cats" ; "at the start")]
+    fn unknown_bangs_without_engine_support(query: &str, expected: &str) -> Result<()> {
+        let resolver = Resolver::builder()
+            .fallback(Fallback::Google { safe_search: None })
+            .build();
+        assert_eq!(expected, resolver.resolve(query)?.url.as_str());
+        Ok(())
+    }
+
+    #[test_case("dcos tokio", "https://docs.rs/tokio/" ; "swapped letters")]
+    #[test_case("docss tokio", "https://docs.rs/tokio/" ; "doubled letter")]
+    #[test_case("Twiir", "https://this-week-in-rust.org/" ; "redirect")]
+    #[test_case("by the way", "https://duckduckgo.com/?k1=-1&q=by+the+way" ; "short keywords are left alone")]
+    fn typos(query: &str, expected: &str) -> Result<()> {
+        assert_eq!(expected, Resolver::default().resolve(query)?.url.as_str());
+        Ok(())
+    }
+
+    #[test_case("app store", &["ap store"] ; "doubled letter in a short keyword")]
+    #[test_case("cord cutting", &["core cutting"] ; "neighbouring key")]
+    #[test_case("fore golf", &["core golf"] ; "neighbouring key at the start")]
+    #[test_case("crate and barrel", &["crates and barrel"] ; "several words")]
+    #[test_case("twit", &["twir"] ; "redirect")]
+    #[test_case("abd", &["abc"] ; "three letter keyword")]
+    fn typos_that_could_be_words(query: &str, expected: &[&str]) -> Result<()> {
+        let resolution = Resolver::default().resolve(query)?;
+        assert_eq!(Rule::Fallback, resolution.kind);
+        assert_eq!(expected, resolution.did_you_mean);
+        Ok(())
+    }
+
+    #[test_case("ghh rust-lang/rust", &["gh rust-lang/rust"] ; "doubled letter in a two letter keyword")]
+    #[test_case("twit 520", &["twir 520"] ; "number")]
+    #[test_case("crate serde@1.0", &["crates serde@1.0"] ; "version")]
+    fn typos_with_arguments(query: &str, expected: &[&str]) {
+        let err = Resolver::default().resolve(query).unwrap_err();
+        assert!(
+            matches!(&err, Error::DidYouMean(queries) if queries == expected),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn did_you_mean() {
+        let err = Resolver::default().resolve("twit 520").unwrap_err();
+        assert_eq!("Did you mean `twir 520`?", err.to_string());
+        assert_eq!(300, err.status());
+    }
+
+    const PATTERNS_TOML: &str = r#"
+        [[shortcut]]
+        keyword = "go"
+        template = "https://keyword.example.com/{rest}"
+
+        [[pattern]]
+        name = "first"
+        pattern = 'go (\d+)'
+        priority = "first"
+        template = "https://first.example.com/{1}"
+
+        [[pattern]]
+        name = "keyword-wins"
+        pattern = 'go (\w+)'
+        template = "https://never.example.com/{1}"
+
+        [[pattern]]
+        name = "before-domain"
+        pattern = '(\w+)\. com'
+        priority = "before_domain"
+        template = "https://before-domain.example.com/{1}"
+    "#;
+
+    #[test_case("go 42", "https://first.example.com/42", Rule::Pattern, Some("first"))]
+    #[test_case(
+        "go rust",
+        "https://keyword.example.com/rust",
+        Rule::Keyword,
+        Some("go")
+    )]
+    #[test_case(
+        "example. com",
+        "https://before-domain.example.com/example",
+        Rule::Pattern,
+        Some("before-domain")
+    )]
+    #[test_case("www.example. com", "https://www.example.com/", Rule::Domain, None)]
+    fn pattern_priority(query: &str, url: &str, kind: Rule, shortcut: Option<&str>) -> Result<()> {
+        let resolver = Resolver::builder()
+            .registry(Registry::from_toml(PATTERNS_TOML)?)
+            .build();
+        let resolution = resolver.resolve(query)?;
+        assert_eq!(url, resolution.url.as_str());
+        assert_eq!(kind, resolution.kind);
+        assert_eq!(shortcut, resolution.shortcut.as_deref());
+        Ok(())
+    }
+
+    #[test_case(
+        Safeguards { open_destinations: false, ..Safeguards::default() },
+        "opening urls, domains and IP addresses is disabled" ; "disabled"
+    )]
+    #[test_case(
+        Safeguards { allow: vec!["example.com".into()], ..Safeguards::default() },
+        "evil.net isn't on the allow list" ; "allow list"
+    )]
+    fn safeguards(safeguards: Safeguards, detail: &str) {
+        let resolver = Resolver::builder().safeguards(safeguards).build();
+        let explanation = resolver.explain("evil.net/login");
+        let step = explanation
+            .steps
+            .iter()
+            .find(|step| step.rule == Rule::Domain)
+            .unwrap();
+        assert!(!step.matched);
+        assert_eq!(detail, step.detail);
+        assert_eq!(
+            Some("https://duckduckgo.com/?k1=-1&q=evil.net%2Flogin"),
+            explanation.url.as_ref().map(Url::as_str)
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::errors::Result;
+use crate::explain::Rule;
+use crate::query::ParsedQuery;
+
+use serde::Serialize;
+use url::Url;
+
+/// Where a shortcut sends a query.
+///
+/// Shortcuts only need to fill in the url; the resolver records which rule
+/// and keyword matched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Resolution {
+    pub url: Url,
+    /// The keyword of the shortcut or bang that matched, if any.
+    pub shortcut: Option<String>,
+    /// The positional arguments passed to the shortcut.
+    pub arguments: Vec<String>,
+    /// The rule that matched.
+    pub kind: Rule,
+    /// Corrected queries for a search that starts with a mistyped keyword,
+    /// eg `crates and barrel` for `crate and barrel`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub did_you_mean: Vec<String>,
+}
+
+impl Resolution {
+    pub(crate) fn matched(
+        mut self,
+        kind: Rule,
+        shortcut: Option<&str>,
+        arguments: &[String],
+    ) -> Self {
+        self.kind = kind;
+        self.shortcut = shortcut.map(str::to_string);
+        self.arguments = arguments.to_vec();
+        self
+    }
+}
+
+impl From<Url> for Resolution {
+    fn from(url: Url) -> Self {
+        Self {
+            url,
+            shortcut: None,
+            arguments: Vec::new(),
+            kind: Rule::Keyword,
+            did_you_mean: Vec::new(),
+        }
+    }
+}
+
+/// Whether a shortcut is used with or without anything after the keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arguments {
+    /// Only the bare keyword matches, eg `twir`.
+    None,
+    /// The keyword must be followed by a separator and a query, eg `gh foo`.
+    Required,
+    /// Either the bare keyword or the keyword followed by a separator and a
+    /// query matches, eg `x` or `x 927`. The query is empty for the former.
+    Optional,
+}
+
+/// A keyword-triggered rule that turns a query into a url.
+///
+/// Implement this to add shortcuts from outside this crate and register them
+/// with [`ResolverBuilder::shortcut`](crate::resolver::ResolverBuilder::shortcut).
+pub trait Shortcut: Send + Sync {
+    /// The keyword followed by any aliases.
+    fn keywords(&self) -> Vec<&str>;
+
+    /// A short, human readable description of where the shortcut goes.
+    fn description(&self) -> &str;
+
+    /// A heading to group the shortcut under on the help page, if any.
+    fn category(&self) -> &str {
+        ""
+    }
+
+    /// Example queries to show on the help page, eg `gh rust-lang/rust`.
+    fn examples(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// The strings that may separate the keyword from the rest of the query.
+    fn separators(&self) -> Vec<&str> {
+        vec![" "]
+    }
+
+    fn arguments(&self) -> Arguments {
+        Arguments::Required
+    }
+
+    fn resolve(&self, query: &ParsedQuery) -> Result<Resolution>;
+}
@@ -0,0 +1,40 @@
+//! Search suggestions in the OpenSearch suggestions format
+//! (`application/x-suggestions+json`), which browsers show in the address
+//! bar as the query is typed.
+
+use serde::ser::{Serialize, Serializer};
+use url::Url;
+
+/// The most suggestions returned for one query.
+pub(crate) const MAX_SUGGESTIONS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The query the browser fills in when the suggestion is picked.
+    pub completion: String,
+    pub description: String,
+    /// Where the completion goes, if it can be resolved without any more
+    /// input.
+    pub url: Option<Url>,
+}
+
+/// The suggestions for a query, which serialize as
+/// `[query, [completions], [descriptions], [urls]]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestions {
+    pub query: String,
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Serialize for Suggestions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let completions: Vec<_> = self.suggestions.iter().map(|s| &s.completion).collect();
+        let descriptions: Vec<_> = self.suggestions.iter().map(|s| &s.description).collect();
+        let urls: Vec<_> = self
+            .suggestions
+            .iter()
+            .map(|s| s.url.as_ref().map(Url::as_str).unwrap_or_default())
+            .collect();
+        (&self.query, completions, descriptions, urls).serialize(serializer)
+    }
+}
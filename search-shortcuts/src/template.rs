@@ -0,0 +1,413 @@
+//! Url templates such as `https://example.com/{1|path}/search?q={rest|query}`.
+//!
+//! A placeholder names a value followed by any number of `|filter`s:
+//!
+//! - `{1}`, `{2}`, ... are the positional arguments
+//! - `{rest}` (or `{query}`) is everything after the keyword
+//! - `{keyword}` is the keyword that was typed
+//! - any other name is the value of a `name:value` modifier
+//!
+//! [Patterns](crate::pattern) fill the same placeholders from their regex's
+//! captures instead.
+//!
+//! The filters `lower`, `upper` and `default:<value>` transform the value in
+//! order. The value is then encoded by at most one of `query` (form encoding,
+//! the default), `path` (a single path segment), `fragment` or `raw` (no
+//! encoding, only for trusted values). Use `{{` and `}}` for literal braces.
+
+use crate::errors::{Error, Result};
+use crate::query::ParsedQuery;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use url::{form_urlencoded, Url};
+
+/// https://url.spec.whatwg.org/#fragment-percent-encode-set plus `#` and `%`
+/// so a value can't end the fragment early or inject escapes.
+const FRAGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`')
+    .add(b'#')
+    .add(b'%');
+
+/// https://url.spec.whatwg.org/#path-percent-encode-set plus `/` and `%` so a
+/// value stays within one segment.
+const PATH_SEGMENT: &AsciiSet = &FRAGMENT.add(b'?').add(b'{').add(b'}').add(b'/');
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Query,
+    Path,
+    Fragment,
+    Raw,
+}
+
+impl Encoding {
+    fn encode(self, value: &str) -> String {
+        match self {
+            Encoding::Query => form_urlencoded::byte_serialize(value.as_bytes()).collect(),
+            Encoding::Path => utf8_percent_encode(value, PATH_SEGMENT).to_string(),
+            Encoding::Fragment => utf8_percent_encode(value, FRAGMENT).to_string(),
+            Encoding::Raw => value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Filter {
+    Lower,
+    Upper,
+    Default(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Placeholder {
+    name: String,
+    filters: Vec<Filter>,
+    encoding: Encoding,
+}
+
+impl Placeholder {
+    fn parse(source: &str) -> Result<Self> {
+        let mut parts = source.split('|');
+        let name = parts.next().unwrap_or_default().trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(invalid(format!("invalid placeholder name {:?}", name)));
+        }
+        if name.parse::<usize>() == Ok(0) {
+            return Err(invalid("positional placeholders start at {1}".into()));
+        }
+
+        let mut filters = Vec::new();
+        let mut encoding = None;
+        for filter in parts {
+            let (filter, arg) = match filter.split_once(':') {
+                Some((filter, arg)) => (filter.trim(), Some(arg)),
+                None => (filter.trim(), None),
+            };
+            let new_encoding = match (filter, arg) {
+                ("lower", None) => {
+                    filters.push(Filter::Lower);
+                    None
+                }
+                ("upper", None) => {
+                    filters.push(Filter::Upper);
+                    None
+                }
+                ("default", Some(arg)) => {
+                    filters.push(Filter::Default(arg.to_string()));
+                    None
+                }
+                ("query", None) => Some(Encoding::Query),
+                ("path", None) => Some(Encoding::Path),
+                ("fragment", None) => Some(Encoding::Fragment),
+                ("raw", None) => Some(Encoding::Raw),
+                _ => return Err(invalid(format!("unknown filter {:?}", filter))),
+            };
+            if new_encoding.is_some() {
+                if encoding.is_some() {
+                    return Err(invalid(format!("{{{}}} has more than one encoding", name)));
+                }
+                encoding = new_encoding;
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            filters,
+            encoding: encoding.unwrap_or(Encoding::Query),
+        })
+    }
+
+    fn lookup(&self, query: &ParsedQuery) -> Option<String> {
+        match self.name.as_str() {
+            "rest" | "query" => Some(query.raw_rest.clone()),
+            "keyword" => Some(query.keyword.clone()),
+            name => match name.parse::<usize>() {
+                Ok(index) => query.arg(index - 1).map(str::to_string),
+                Err(_) => query.modifier(name).map(str::to_string),
+            },
+        }
+    }
+
+    fn render(&self, value: Option<String>) -> Result<String> {
+        let mut value = value;
+        for filter in &self.filters {
+            value = match (filter, value) {
+                (Filter::Lower, value) => value.map(|value| value.to_lowercase()),
+                (Filter::Upper, value) => value.map(|value| value.to_uppercase()),
+                (Filter::Default(default), value) => match value {
+                    Some(value) if !value.is_empty() => Some(value),
+                    _ => Some(default.clone()),
+                },
+            };
+        }
+        match value {
+            Some(value) => Ok(self.encoding.encode(&value)),
+            None => Err(Error::MissingArgument(self.name.clone())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A parsed url template, see the [module docs](self) for the syntax.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') => return Err(invalid("nested '{'".into())),
+                            Some(c) => placeholder.push(c),
+                            None => return Err(invalid("unclosed '{'".into())),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Placeholder(Placeholder::parse(&placeholder)?));
+                }
+                '}' => return Err(invalid("unmatched '}'".into())),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        let template = Self {
+            source: source.to_string(),
+            parts,
+        };
+        // make sure that the template produces an absolute url
+        template
+            .render_with(|_| Some("test".to_string()))
+            .map_err(|err| invalid(format!("{:?} doesn't produce a url: {}", source, err)))?;
+        Ok(template)
+    }
+
+    /// Builds a template from a url that marks the search terms some other
+    /// way, eg Firefox's `%s`. Each marker becomes `{rest}` with the given
+    /// encoding or, if there isn't one, the encoding for the part of the url
+    /// the marker is in.
+    pub(crate) fn from_markers(url: &str, markers: &[(&str, Option<&str>)]) -> Result<Self> {
+        let mut source = String::new();
+        let mut rest = url;
+        let mut encoding = "path";
+        'outer: while let Some(c) = rest.chars().next() {
+            for (marker, marker_encoding) in markers {
+                if let Some(after) = rest.strip_prefix(marker) {
+                    source.push_str(&format!("{{rest|{}}}", marker_encoding.unwrap_or(encoding)));
+                    rest = after;
+                    continue 'outer;
+                }
+            }
+            match c {
+                '{' => source.push_str("{{"),
+                '}' => source.push_str("}}"),
+                c => {
+                    if c == '?' && encoding == "path" {
+                        encoding = "query";
+                    } else if c == '#' {
+                        encoding = "fragment";
+                    }
+                    source.push(c);
+                }
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+        Self::parse(&source)
+    }
+
+    /// The names of the values the template uses, eg `1` or `rest`.
+    pub(crate) fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Placeholder(placeholder) => Some(placeholder.name.as_str()),
+            Part::Literal(_) => None,
+        })
+    }
+
+    /// How many positional arguments the template uses, or `None` if it uses
+    /// the whole query.
+    pub(crate) fn arguments(&self) -> Option<usize> {
+        let mut arguments = 0;
+        for name in self.placeholders() {
+            match name {
+                "rest" | "query" => return None,
+                name => arguments = arguments.max(name.parse().unwrap_or(0)),
+            }
+        }
+        Some(arguments)
+    }
+
+    pub fn render(&self, query: &ParsedQuery) -> Result<Url> {
+        self.render_with(|placeholder| placeholder.lookup(query))
+    }
+
+    fn render_with(&self, lookup: impl Fn(&Placeholder) -> Option<String>) -> Result<Url> {
+        let mut url = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => url.push_str(literal),
+                Part::Placeholder(placeholder) => {
+                    url.push_str(&placeholder.render(lookup(placeholder))?)
+                }
+            }
+        }
+        Ok(Url::parse(&url)?)
+    }
+}
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::parse(source)
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        Self::parse(&source)
+    }
+}
+
+impl From<Template> for String {
+    fn from(template: Template) -> Self {
+        template.source
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn invalid(reason: String) -> Error {
+    Error::InvalidTemplate(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(
+        "https://example.com/?q={rest}",
+        "lol donkey",
+        "https://example.com/?q=lol+donkey"
+    )]
+    #[test_case("https://example.com/?q={query}", "a/b", "https://example.com/?q=a%2Fb" ; "query alias")]
+    #[test_case(
+        "https://example.com/{1|path}",
+        "a/b?c",
+        "https://example.com/a%2Fb%3Fc"
+    )]
+    #[test_case(
+        "https://example.com/{1|path}/{2|path}",
+        "a b",
+        "https://example.com/a/b"
+    )]
+    #[test_case(
+        "https://example.com/#/{rest|fragment}",
+        "a#b c",
+        "https://example.com/#/a%23b%20c"
+    )]
+    #[test_case("https://example.com/{1|raw}", "a/b", "https://example.com/a/b")]
+    #[test_case("https://example.com/{1|lower|path}", "ABC", "https://example.com/abc")]
+    #[test_case("https://example.com/{1|upper|path}", "abc", "https://example.com/ABC")]
+    #[test_case("https://example.com/{2|default:x|path}", "a", "https://example.com/x")]
+    #[test_case("https://example.com/?l={lang|default:en}", "a", "https://example.com/?l=en" ; "default modifier")]
+    #[test_case("https://example.com/?l={lang}", "a lang:de", "https://example.com/?l=de" ; "modifier")]
+    #[test_case("https://example.com/{keyword|path}", "a", "https://example.com/kw")]
+    #[test_case("https://example.com/{{x}}?q={1}", "a", "https://example.com/%7Bx%7D?q=a" ; "escaped braces")]
+    fn render(template: &str, rest: &str, expected: &str) -> Result<()> {
+        let template = Template::parse(template)?;
+        let actual = template.render(&ParsedQuery::new("kw", rest))?;
+        assert_eq!(expected, actual.as_str());
+        Ok(())
+    }
+
+    #[test_case(
+        "https://example.com/search?q=%s",
+        "https://example.com/search?q={rest|query}"
+    )]
+    #[test_case(
+        "https://example.com/%s/?x=%s#%s",
+        "https://example.com/{rest|path}/?x={rest|query}#{rest|fragment}"
+    )]
+    #[test_case("https://example.com/{x}/%S", "https://example.com/{{x}}/{rest|raw}")]
+    fn from_markers(url: &str, expected: &str) -> Result<()> {
+        let template = Template::from_markers(url, &[("%s", None), ("%S", Some("raw"))])?;
+        assert_eq!(expected, template.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn missing_argument() -> Result<()> {
+        let template = Template::parse("https://example.com/{2}")?;
+        let err = template.render(&ParsedQuery::new("kw", "a")).unwrap_err();
+        assert!(matches!(err, Error::MissingArgument(name) if name == "2"));
+        Ok(())
+    }
+
+    #[test_case("https://example.com/", Some(0))]
+    #[test_case("https://example.com/{2}/{1|path}", Some(2))]
+    #[test_case("https://example.com/{1}?l={lang}", Some(1) ; "modifier")]
+    #[test_case("https://example.com/{1}?q={rest}", None ; "whole query")]
+    fn arguments(template: &str, expected: Option<usize>) -> Result<()> {
+        assert_eq!(expected, Template::parse(template)?.arguments());
+        Ok(())
+    }
+
+    #[test_case("https://example.com/{" ; "unclosed")]
+    #[test_case("https://example.com/}" ; "unmatched")]
+    #[test_case("https://example.com/{a{b}}" ; "nested")]
+    #[test_case("https://example.com/{}" ; "empty name")]
+    #[test_case("https://example.com/{0}" ; "zero")]
+    #[test_case("https://example.com/{1|base64}" ; "unknown filter")]
+    #[test_case("https://example.com/{1|path|query}" ; "two encodings")]
+    #[test_case("https://example.com/{1|default}" ; "default without value")]
+    #[test_case("example.com/?q={rest}" ; "relative")]
+    fn invalid_template(template: &str) {
+        assert!(Template::parse(template).is_err(), "{:?}", template);
+    }
+}
@@ -10,8 +10,8 @@ pub(crate) enum Error {
     EnvVarInvalidUtf8(&'static str),
     #[error("IoError: {0}")]
     Io(#[from] std::io::Error),
-    #[error("Error determining redirect url: {0}")]
-    Url(#[from] search_shortcuts::errors::Error),
+    #[error("Error loading shortcuts: {0}")]
+    Registry(search_shortcuts::errors::Error),
     #[error("Error configuring TLS: {0}")]
     Tls(&'static str),
 }
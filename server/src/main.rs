@@ -1,33 +1,130 @@
 mod errors;
 use errors::{Error, Result};
 
+use actix_web::http::StatusCode;
 use actix_web::middleware::{Compress, DefaultHeaders, Logger};
-use actix_web::web::Query;
-use actix_web::{get, App, HttpResponse, HttpServer};
+use actix_web::web::{Data, Json, Query};
+use actix_web::{
+    get, post, route, App, HttpRequest, HttpResponse, HttpResponseBuilder, HttpServer,
+};
 use itertools::Itertools;
 use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod};
-use search_shortcuts::query_to_url;
+use search_shortcuts::api::{self, ApiError, BatchRequest};
+use search_shortcuts::errors::Error as ShortcutsError;
+use search_shortcuts::{help, BangPack, Registry, Resolver};
 use serde::Deserialize;
 use std::env;
 
 #[derive(Debug, Deserialize)]
 struct Args {
     q: Option<String>,
+    /// Shows how the query resolves instead of redirecting, as JSON for
+    /// `explain=json` and HTML otherwise.
+    explain: Option<String>,
 }
 
 #[get("/")]
-async fn index(args: Query<Args>) -> Result<HttpResponse> {
-    Ok(match &args.q {
-        Some(query) => {
-            let redirect_url = query_to_url(query)?;
-            HttpResponse::SeeOther()
-                .append_header(("Location", redirect_url.as_str()))
-                .finish()
-        }
+async fn index(req: HttpRequest, args: Query<Args>, resolver: Data<Resolver>) -> HttpResponse {
+    let sec_fetch_site = req
+        .headers()
+        .get("Sec-Fetch-Site")
+        .and_then(|value| value.to_str().ok());
+    match &args.q {
+        Some(query) if args.explain.is_some() => explain(&resolver, query, args.explain.as_deref()),
+        Some(query) if help::is_help_query(&resolver, query) => help_page(&resolver),
+        Some(query) => match resolver.resolve(query) {
+            Ok(resolution)
+                if resolver
+                    .safeguards()
+                    .needs_interstitial(&resolution, sec_fetch_site) =>
+            {
+                HttpResponse::Ok()
+                    .content_type("text/html")
+                    .body(help::render_interstitial(&resolver, query, &resolution.url))
+            }
+            Ok(resolution) => HttpResponse::SeeOther()
+                .append_header(("Location", resolution.url.as_str()))
+                .finish(),
+            Err(err) => error_page(&resolver, query, &err),
+        },
         None => HttpResponse::Ok()
             .content_type("text/html")
             .body(include_str!("../../resources/index.html")),
-    })
+    }
+}
+
+fn error_page(resolver: &Resolver, query: &str, err: &ShortcutsError) -> HttpResponse {
+    if !err.is_user_error() {
+        log::error!("Error resolving {:?}: {}", query, err);
+    }
+    let status = StatusCode::from_u16(err.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    HttpResponse::build(status)
+        .content_type("text/html")
+        .body(help::render_error(resolver, query, err))
+}
+
+fn explain(resolver: &Resolver, query: &str, format: Option<&str>) -> HttpResponse {
+    let explanation = resolver.explain(query);
+    match format {
+        Some("json") => HttpResponse::Ok().json(explanation),
+        _ => HttpResponse::Ok()
+            .content_type("text/html")
+            .body(explanation.to_html()),
+    }
+}
+
+#[get("/help")]
+async fn show_help(resolver: Data<Resolver>) -> HttpResponse {
+    help_page(&resolver)
+}
+
+fn help_page(resolver: &Resolver) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html")
+        .body(help::render(resolver))
+}
+
+#[get("/suggest")]
+async fn suggest(args: Query<Args>, resolver: Data<Resolver>) -> HttpResponse {
+    let query = args.q.as_deref().unwrap_or_default();
+    HttpResponse::Ok()
+        .content_type("application/x-suggestions+json")
+        .json(resolver.suggest(query))
+}
+
+fn cors(mut response: HttpResponseBuilder) -> HttpResponseBuilder {
+    for header in api::CORS_HEADERS {
+        response.append_header(*header);
+    }
+    response
+}
+
+#[get("/api/resolve")]
+async fn api_resolve(args: Query<Args>, resolver: Data<Resolver>) -> HttpResponse {
+    let query = match &args.q {
+        Some(query) => query,
+        None => return cors(HttpResponse::BadRequest()).json(ApiError::new("missing q parameter")),
+    };
+    let result = api::resolve(&resolver, query);
+    let response = if result.is_ok() {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::UnprocessableEntity()
+    };
+    cors(response).json(result)
+}
+
+#[post("/api/resolve")]
+async fn api_resolve_batch(batch: Json<BatchRequest>, resolver: Data<Resolver>) -> HttpResponse {
+    match api::resolve_batch(&resolver, &batch) {
+        Ok(results) => cors(HttpResponse::Ok()).json(results),
+        Err(err) => cors(HttpResponse::PayloadTooLarge()).json(err),
+    }
+}
+
+#[route("/api/resolve", method = "OPTIONS")]
+async fn api_preflight() -> HttpResponse {
+    cors(HttpResponse::NoContent()).finish()
 }
 
 #[get("/osdf.xml")]
@@ -68,6 +165,28 @@ fn setup_ssl() -> Result<SslAcceptorBuilder> {
     Ok(builder)
 }
 
+fn load_registry() -> Result<Registry> {
+    let mut registry = match env::var("SHORTCUTS_FILE") {
+        Ok(filename) => Registry::from_path(filename).map_err(Error::Registry)?,
+        Err(env::VarError::NotPresent) => Registry::default(),
+        Err(env::VarError::NotUnicode(_)) => {
+            return Err(Error::EnvVarInvalidUtf8("SHORTCUTS_FILE"))
+        }
+    };
+    match env::var("BANGS_FILE") {
+        Ok(filename) => {
+            let bangs = BangPack::from_path(filename).map_err(Error::Registry)?;
+            log::info!("Loaded {} bangs ({} skipped)", bangs.len(), bangs.skipped());
+            for conflict in registry.add_bangs(bangs) {
+                log::warn!("{}", conflict);
+            }
+        }
+        Err(env::VarError::NotPresent) => {}
+        Err(env::VarError::NotUnicode(_)) => return Err(Error::EnvVarInvalidUtf8("BANGS_FILE")),
+    }
+    Ok(registry)
+}
+
 fn default_headers() -> DefaultHeaders {
     let features = [
         "accelerometer",
@@ -142,13 +261,20 @@ async fn main() -> Result<()> {
     };
 
     let ssl_builder = setup_ssl()?;
+    let resolver = Data::new(Resolver::builder().registry(load_registry()?).build());
 
-    HttpServer::new(|| {
+    HttpServer::new(move || {
         App::new()
+            .app_data(resolver.clone())
             .wrap(Logger::new(r#"%s %b "%{User-Agent}i" %T"#))
             .wrap(Compress::default())
             .wrap(default_headers())
             .service(index)
+            .service(show_help)
+            .service(suggest)
+            .service(api_resolve)
+            .service(api_resolve_batch)
+            .service(api_preflight)
             .service(osdf)
     })
     .bind_openssl(bind_addr, ssl_builder)?